@@ -0,0 +1,180 @@
+// Chat attachments - resolve, size-check and stage files for the CLI
+
+use serde::{Deserialize, Serialize};
+use ssh2::Session;
+use std::io::{Read, Write};
+use std::path::Path;
+
+pub const MAX_ATTACHMENT_BYTES: u64 = 10 * 1024 * 1024;
+pub const MAX_TOTAL_ATTACHMENT_BYTES: u64 = 25 * 1024 * 1024;
+
+/// Where local files are staged on the SSH host before the CLI reads them
+const REMOTE_STAGING_DIR: &str = "/tmp/nexus-attachments";
+
+/// Staged files older than this are deleted the next time one is uploaded.
+/// Regenerating a reply reuses its staged files, so they are not removed
+/// as soon as the reply arrives.
+const STAGED_MAX_AGE_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentRef {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub mime_type: String,
+    pub size: u64,
+    pub path: String,                   // Path as given by the UI
+    pub cli_path: String,               // Path passed to the CLI on the target host
+    pub uploaded: bool,                 // Copied from the desktop to the SSH host
+}
+
+fn mime_type_for(name: &str) -> &'static str {
+    let ext = Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "md" => "text/markdown",
+        "txt" | "log" | "rs" | "ts" | "tsx" | "js" | "jsx" | "py" | "go" | "toml" | "yaml" | "yml"
+        | "html" | "css" | "sh" | "c" | "h" | "cpp" | "java" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+fn check_size(path: &str, size: u64) -> Result<(), String> {
+    if size > MAX_ATTACHMENT_BYTES {
+        return Err(format!(
+            "Attachment {} is {} bytes, over the {} byte limit",
+            path, size, MAX_ATTACHMENT_BYTES
+        ));
+    }
+    Ok(())
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+/// Copy a local file into the staging directory on the SSH host
+fn upload(sess: &Session, local: &Path, remote: &str) -> Result<(), String> {
+    let sftp = sess.sftp().map_err(|e| e.to_string())?;
+    // The staging directory usually exists already
+    let _ = sftp.mkdir(Path::new(REMOTE_STAGING_DIR), 0o700);
+    prune_staged(&sftp);
+
+    let mut src = std::fs::File::open(local).map_err(|e| e.to_string())?;
+    let mut dst = sftp.create(Path::new(remote)).map_err(|e| e.to_string())?;
+    let mut buf = [0u8; 32 * 1024];
+    loop {
+        let n = src.read(&mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        dst.write_all(&buf[..n]).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Delete staged uploads older than `STAGED_MAX_AGE_SECS`
+fn prune_staged(sftp: &ssh2::Sftp) {
+    let Ok(entries) = sftp.readdir(Path::new(REMOTE_STAGING_DIR)) else { return };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    for (path, stat) in entries {
+        let old = stat.mtime.is_some_and(|mtime| now.saturating_sub(mtime) > STAGED_MAX_AGE_SECS);
+        if stat.is_file() && old {
+            let _ = sftp.unlink(&path);
+        }
+    }
+}
+
+/// Resolve each path to something the CLI can read on the target host.
+///
+/// Paths that exist on the desktop are uploaded over SFTP when `session` is
+/// set and used in place otherwise. Anything else is treated as a path on
+/// the remote host, relative to `project` when not absolute. Without a
+/// session relative paths are taken from `project` as well.
+pub fn prepare(paths: &[String], project: Option<&Path>, session: Option<&Session>) -> Result<Vec<AttachmentRef>, String> {
+    let mut refs = Vec::new();
+    let mut total = 0u64;
+
+    for path in paths {
+        let id = uuid::Uuid::new_v4().to_string();
+        let name = file_name(path);
+        let given = Path::new(path);
+        // Without SSH the project is on this machine too
+        let in_project = match project {
+            Some(root) if session.is_none() && given.is_relative() => Some(root.join(given)),
+            _ => None,
+        };
+        let local = in_project.as_deref().unwrap_or(given);
+
+        let (size, cli_path, uploaded) = match std::fs::metadata(local) {
+            Ok(meta) if meta.is_file() => {
+                check_size(path, meta.len())?;
+                match session {
+                    Some(sess) => {
+                        let remote = format!("{}/{}-{}", REMOTE_STAGING_DIR, id, name);
+                        upload(sess, local, &remote)
+                            .map_err(|e| format!("Failed to upload {}: {}", path, e))?;
+                        (meta.len(), remote, true)
+                    }
+                    None => {
+                        let canonical = local.canonicalize().map_err(|e| e.to_string())?;
+                        (meta.len(), canonical.to_string_lossy().to_string(), false)
+                    }
+                }
+            }
+            Ok(_) => return Err(format!("Attachment is not a file: {}", path)),
+            Err(_) => {
+                let sess = session.ok_or_else(|| format!("Attachment not found: {}", path))?;
+                let remote = match project {
+                    Some(root) if local.is_relative() => root.join(local),
+                    _ => local.to_path_buf(),
+                };
+                let sftp = sess.sftp().map_err(|e| e.to_string())?;
+                let stat = sftp
+                    .stat(&remote)
+                    .map_err(|_| format!("Attachment not found locally or on the remote host: {}", path))?;
+                if !stat.is_file() {
+                    return Err(format!("Attachment is not a file: {}", path));
+                }
+                let size = stat.size.unwrap_or(0);
+                check_size(path, size)?;
+                (size, remote.to_string_lossy().to_string(), false)
+            }
+        };
+
+        total += size;
+        if total > MAX_TOTAL_ATTACHMENT_BYTES {
+            return Err(format!(
+                "Attachments exceed the {} byte total limit",
+                MAX_TOTAL_ATTACHMENT_BYTES
+            ));
+        }
+
+        refs.push(AttachmentRef {
+            id,
+            mime_type: mime_type_for(&name).to_string(),
+            name,
+            size,
+            path: path.clone(),
+            cli_path,
+            uploaded,
+        });
+    }
+    Ok(refs)
+}
@@ -0,0 +1,263 @@
+// Spending budgets per provider and per project, checked against the usage ledger
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::Manager;
+
+use crate::usage::{self, UsageRecord};
+
+/// Completion length assumed when estimating the cost of a request up front
+const EXPECTED_COMPLETION_TOKENS: u64 = 1000;
+
+pub fn default_thresholds() -> Vec<f64> {
+    vec![0.5, 0.8, 1.0]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Budget {
+    pub scope: String,                  // "provider" or "project"
+    pub target: String,                 // Provider name or project path
+    pub daily_limit: Option<f64>,       // USD
+    pub monthly_limit: Option<f64>,     // USD
+    #[serde(default = "default_thresholds")]
+    pub warn_thresholds: Vec<f64>,      // Fractions of a limit that trigger a warning
+}
+
+/// Budgets plus the warnings already sent, so each threshold fires once per period
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BudgetBook {
+    pub budgets: Vec<Budget>,
+    #[serde(default)]
+    pub warnings_sent: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetStatus {
+    pub budget: Budget,
+    pub daily_spent: f64,
+    pub monthly_spent: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostEstimate {
+    pub model: Option<String>,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+}
+
+/// Who is about to spend: the provider and project a request runs under
+pub struct SpendContext<'a> {
+    pub provider: Option<&'a str>,
+    pub project: Option<&'a str>,
+}
+
+pub fn estimate(model: Option<&str>, prompt: &str) -> CostEstimate {
+    let prompt_tokens = usage::estimate_tokens(prompt);
+    CostEstimate {
+        model: model.map(|m| m.to_string()),
+        prompt_tokens,
+        completion_tokens: EXPECTED_COMPLETION_TOKENS,
+        cost: usage::cost_for(model, prompt_tokens + EXPECTED_COMPLETION_TOKENS),
+    }
+}
+
+impl Budget {
+    fn applies_to(&self, ctx: &SpendContext) -> bool {
+        match self.scope.as_str() {
+            "provider" => ctx.provider == Some(self.target.as_str()),
+            "project" => ctx.project == Some(self.target.as_str()),
+            _ => false,
+        }
+    }
+
+    fn matches(&self, record: &UsageRecord) -> bool {
+        match self.scope.as_str() {
+            "provider" => record.usage.provider.as_deref() == Some(self.target.as_str()),
+            "project" => record.project.as_deref() == Some(self.target.as_str()),
+            _ => false,
+        }
+    }
+
+    /// Spend within the period identified by a timestamp prefix
+    /// ("2026-10-18" for a day, "2026-10" for a month)
+    fn spent(&self, ledger: &[UsageRecord], period: &str) -> f64 {
+        ledger
+            .iter()
+            .filter(|r| r.timestamp.starts_with(period) && self.matches(r))
+            .map(|r| r.usage.cost)
+            .sum()
+    }
+
+    fn periods(&self) -> [(&'static str, Option<f64>, String); 2] {
+        let now = chrono::Utc::now();
+        [
+            ("Daily", self.daily_limit, now.format("%Y-%m-%d").to_string()),
+            ("Monthly", self.monthly_limit, now.format("%Y-%m").to_string()),
+        ]
+    }
+}
+
+pub fn status(book: &BudgetBook, ledger: &[UsageRecord]) -> Vec<BudgetStatus> {
+    let now = chrono::Utc::now();
+    book.budgets
+        .iter()
+        .map(|b| BudgetStatus {
+            budget: b.clone(),
+            daily_spent: b.spent(ledger, &now.format("%Y-%m-%d").to_string()),
+            monthly_spent: b.spent(ledger, &now.format("%Y-%m").to_string()),
+        })
+        .collect()
+}
+
+/// Refuse a request whose estimated cost would bring any applicable budget
+/// to its limit or past it.
+pub fn check(book: &BudgetBook, ledger: &[UsageRecord], ctx: &SpendContext, estimated_cost: f64) -> Result<(), String> {
+    for budget in book.budgets.iter().filter(|b| b.applies_to(ctx)) {
+        for (label, limit, period) in budget.periods() {
+            let Some(limit) = limit else { continue };
+            let spent = budget.spent(ledger, &period);
+            if spent + estimated_cost >= limit {
+                return Err(format!(
+                    "{} budget for {} '{}' exceeded: ${:.4} spent of ${:.2} (this request ~${:.4})",
+                    label, budget.scope, budget.target, spent, limit, estimated_cost
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Warnings for thresholds crossed since the last call. Crossed thresholds
+/// are remembered in the book so they are reported once per period.
+pub fn new_warnings(book: &mut BudgetBook, ledger: &[UsageRecord]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for budget in &book.budgets {
+        for (label, limit, period) in budget.periods() {
+            let Some(limit) = limit.filter(|l| *l > 0.0) else { continue };
+            let spent = budget.spent(ledger, &period);
+            for threshold in &budget.warn_thresholds {
+                if spent < limit * threshold {
+                    continue;
+                }
+                let key = format!("{}:{}:{}:{}", budget.scope, budget.target, period, threshold);
+                if book.warnings_sent.contains(&key) {
+                    continue;
+                }
+                book.warnings_sent.push(key);
+                warnings.push(format!(
+                    "{} budget for {} '{}' at {:.0}%: ${:.4} of ${:.2}",
+                    label, budget.scope, budget.target, threshold * 100.0, spent, limit
+                ));
+            }
+        }
+    }
+    // Only the current day and month can still fire
+    let now = chrono::Utc::now();
+    let (day, month) = (now.format("%Y-%m-%d").to_string(), now.format("%Y-%m").to_string());
+    book.warnings_sent.retain(|k| {
+        k.split(':').rev().nth(1).is_some_and(|p| p == day || p == month)
+    });
+    warnings
+}
+
+// ============================================================================
+// Persistence
+// ============================================================================
+
+fn book_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir.join("budgets.json"))
+}
+
+pub fn load(app: &tauri::AppHandle) -> BudgetBook {
+    book_path(app)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(app: &tauri::AppHandle, book: &BudgetBook) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(book).map_err(|e| e.to_string())?;
+    std::fs::write(book_path(app)?, json).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::usage::MessageUsage;
+
+    fn spend(provider: &str, cost: f64) -> UsageRecord {
+        UsageRecord {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            source: "chat".to_string(),
+            conversation_id: None,
+            message_id: None,
+            project: Some("/srv/app".to_string()),
+            usage: MessageUsage { provider: Some(provider.to_string()), cost, ..MessageUsage::default() },
+        }
+    }
+
+    fn daily(scope: &str, target: &str, limit: f64) -> Budget {
+        Budget {
+            scope: scope.to_string(),
+            target: target.to_string(),
+            daily_limit: Some(limit),
+            monthly_limit: None,
+            warn_thresholds: default_thresholds(),
+        }
+    }
+
+    #[test]
+    fn each_threshold_warns_once_per_period() {
+        let mut book = BudgetBook { budgets: vec![daily("provider", "openai", 1.0)], ..BudgetBook::default() };
+        let mut ledger = vec![spend("openai", 0.5), spend("anthropic", 5.0)];
+
+        let warnings = new_warnings(&mut book, &ledger);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Daily budget for provider 'openai' at 50%"), "{}", warnings[0]);
+        assert!(new_warnings(&mut book, &ledger).is_empty());
+
+        // Crossing two thresholds at once reports both
+        ledger.push(spend("openai", 0.5));
+        let warnings = new_warnings(&mut book, &ledger);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[1].contains("at 100%"));
+        assert!(new_warnings(&mut book, &ledger).is_empty());
+    }
+
+    #[test]
+    fn warnings_from_past_periods_are_forgotten() {
+        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        let mut book = BudgetBook {
+            budgets: vec![daily("provider", "openai", 1.0)],
+            warnings_sent: vec![
+                "provider:openai:2000-01-01:0.5".to_string(),
+                format!("provider:openai:{}:0.5", today),
+            ],
+        };
+        // Today's 50% warning was already sent; nothing new is crossed
+        assert!(new_warnings(&mut book, &[spend("openai", 0.5)]).is_empty());
+        assert_eq!(book.warnings_sent, [format!("provider:openai:{}:0.5", today)]);
+    }
+
+    #[test]
+    fn requests_are_refused_once_they_reach_the_limit() {
+        let book = BudgetBook {
+            budgets: vec![daily("provider", "openai", 1.0), daily("project", "/srv/app", 10.0)],
+            ..BudgetBook::default()
+        };
+        let ledger = [spend("openai", 0.5), spend("openai", 0.25)];
+        let ctx = SpendContext { provider: Some("openai"), project: Some("/srv/app") };
+
+        assert!(check(&book, &ledger, &ctx, 0.125).is_ok());
+        let error = check(&book, &ledger, &ctx, 0.25).unwrap_err();
+        assert!(error.starts_with("Daily budget for provider 'openai' exceeded"), "{}", error);
+
+        // Budgets for another provider don't apply
+        let other = SpendContext { provider: Some("anthropic"), project: None };
+        assert!(check(&book, &ledger, &other, 0.5).is_ok());
+    }
+}
@@ -0,0 +1,182 @@
+// Conversation tree - chat history with parent links and sibling branches
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::Manager;
+
+use crate::attachments::AttachmentRef;
+use crate::usage::MessageUsage;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessageRecord {
+    pub id: String,
+    pub role: String,
+    pub content: String,
+    pub timestamp: String,
+    pub is_streaming: bool,
+    #[serde(default)]
+    pub parent_id: Option<String>,      // None for the first message of a branch root
+    #[serde(default)]
+    pub model: Option<String>,          // Model override used to produce this message
+    #[serde(default)]
+    pub usage: Option<MessageUsage>,    // Tokens, latency and cost for assistant replies
+    #[serde(default)]
+    pub attachments: Vec<AttachmentRef>,
+}
+
+impl ChatMessageRecord {
+    pub fn new(role: &str, content: String, parent_id: Option<String>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            role: role.to_string(),
+            content,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            is_streaming: false,
+            parent_id,
+            model: None,
+            usage: None,
+            attachments: Vec::new(),
+        }
+    }
+}
+
+/// Position of a message among the alternatives that share its parent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchInfo {
+    pub message_id: String,
+    pub siblings: Vec<String>,
+    pub index: usize,
+}
+
+/// A chat conversation stored as a tree. Editing or regenerating a message
+/// adds a sibling instead of overwriting, and `active_leaf` selects which
+/// branch is shown.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    pub id: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub messages: Vec<ChatMessageRecord>,
+    pub active_leaf: Option<String>,
+}
+
+impl Conversation {
+    pub fn new() -> Self {
+        let now = chrono::Utc::now().to_rfc3339();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            created_at: now.clone(),
+            updated_at: now,
+            messages: Vec::new(),
+            active_leaf: None,
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ChatMessageRecord> {
+        self.messages.iter().find(|m| m.id == id)
+    }
+
+    /// Append a message and make it the tip of the active branch
+    pub fn push(&mut self, msg: ChatMessageRecord) {
+        self.active_leaf = Some(msg.id.clone());
+        self.updated_at = chrono::Utc::now().to_rfc3339();
+        self.messages.push(msg);
+    }
+
+    /// Messages from the root down to the active leaf
+    pub fn active_path(&self) -> Vec<&ChatMessageRecord> {
+        let mut path = Vec::new();
+        let mut cursor = self.active_leaf.clone();
+        while let Some(id) = cursor {
+            match self.get(&id) {
+                Some(msg) => {
+                    cursor = msg.parent_id.clone();
+                    path.push(msg);
+                }
+                None => break,
+            }
+        }
+        path.reverse();
+        path
+    }
+
+    /// Children of `parent_id` (or the roots when `None`) in insertion order
+    pub fn children(&self, parent_id: Option<&str>) -> Vec<&ChatMessageRecord> {
+        self.messages
+            .iter()
+            .filter(|m| m.parent_id.as_deref() == parent_id)
+            .collect()
+    }
+
+    pub fn branch_info(&self, id: &str) -> Result<BranchInfo, String> {
+        let msg = self.get(id).ok_or_else(|| format!("Message not found: {}", id))?;
+        let siblings: Vec<String> = self
+            .children(msg.parent_id.as_deref())
+            .into_iter()
+            .map(|m| m.id.clone())
+            .collect();
+        let index = siblings.iter().position(|s| s == id).unwrap_or(0);
+        Ok(BranchInfo {
+            message_id: id.to_string(),
+            siblings,
+            index,
+        })
+    }
+
+    /// Make the branch through `id` active, following the newest child at
+    /// every level below it.
+    pub fn switch_to(&mut self, id: &str) -> Result<(), String> {
+        if self.get(id).is_none() {
+            return Err(format!("Message not found: {}", id));
+        }
+        let mut leaf = id.to_string();
+        while let Some(child) = self.children(Some(&leaf)).last() {
+            leaf = child.id.clone();
+        }
+        self.active_leaf = Some(leaf);
+        self.updated_at = chrono::Utc::now().to_rfc3339();
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Persistence
+// ============================================================================
+
+fn conversations_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("conversations");
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+pub fn save(app: &tauri::AppHandle, conversation: &Conversation) -> Result<(), String> {
+    let path = conversations_dir(app)?.join(format!("{}.json", conversation.id));
+    let json = serde_json::to_string_pretty(conversation).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| e.to_string())
+}
+
+pub fn load_all(app: &tauri::AppHandle) -> Vec<Conversation> {
+    let Ok(dir) = conversations_dir(app) else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|e| std::fs::read_to_string(e.path()).ok())
+        .filter_map(|raw| serde_json::from_str::<Conversation>(&raw).ok())
+        .collect()
+}
+
+/// The most recently updated conversation, restored on startup
+pub fn load_latest(app: &tauri::AppHandle) -> Option<Conversation> {
+    load_all(app)
+        .into_iter()
+        .max_by(|a, b| a.updated_at.cmp(&b.updated_at))
+}
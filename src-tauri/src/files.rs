@@ -0,0 +1,613 @@
+// Project files - typed directory listings and file reads and writes over
+// SFTP on the SSH session, or std::fs when working locally
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use ssh2::Session;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::State;
+
+use crate::gitignore::Gitignore;
+use crate::projects::check_allowed;
+use crate::{establish_ssh, shell_quote, with_ssh_session, NexusState};
+
+/// Directories with more entries than this are cut short, so one huge
+/// folder can't stall the tree
+const MAX_ENTRIES: usize = 2000;
+
+/// Deepest listing a single call returns; deeper levels load on expand
+const MAX_DEPTH: u32 = 8;
+
+/// Largest read returned in one call; bigger files are read in ranges
+const MAX_READ_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Largest file `write_file` accepts
+const MAX_WRITE_BYTES: usize = 10 * 1024 * 1024;
+
+/// How much of a file is inspected for NUL bytes when detecting binaries
+pub const BINARY_SNIFF_BYTES: usize = 8000;
+
+/// Mirrors `FileNode` in src/types/index.ts
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileNode {
+    pub id: String,                     // The path, unique within a tree
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: &'static str,             // "file" or "directory"
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<FileNode>>,    // None for directories not loaded yet
+    pub size: Option<u64>,
+    pub last_modified: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub is_ignored: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,                // More than MAX_ENTRIES children
+}
+
+/// One directory entry, whichever side it came from
+pub struct Entry {
+    pub name: String,
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    pub size: Option<u64>,
+    pub modified: Option<i64>,          // Unix seconds
+}
+
+/// How a listing walks: where `.gitignore` rules are rooted and whether
+/// ignored entries are shown (flagged) or left out
+struct Walk<'a> {
+    root: &'a Path,
+    ignore: Gitignore,
+    include_ignored: bool,
+}
+
+impl Walk<'_> {
+    /// Path relative to the ignore root, `/`-separated
+    fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(self.root)
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default()
+    }
+
+    /// Turn raw entries into sorted nodes, recursing through `read` while
+    /// `depth` allows. Symlinked directories are never followed. Entries
+    /// are sorted before a large directory is cut short, so the first
+    /// `MAX_ENTRIES` in display order are kept.
+    fn build(
+        &self,
+        dir: &Path,
+        mut entries: Vec<Entry>,
+        depth: u32,
+        read: &mut dyn FnMut(&Path) -> Result<Vec<Entry>, String>,
+    ) -> (Vec<FileNode>, bool) {
+        entries.sort_by_cached_key(|e| (!e.is_dir, e.name.to_lowercase()));
+        let mut nodes = Vec::new();
+        let mut truncated = false;
+        for entry in entries {
+            let path = dir.join(&entry.name);
+            let is_ignored = self.ignore.is_ignored(&self.relative(&path), entry.is_dir);
+            if is_ignored && !self.include_ignored {
+                continue;
+            }
+            if nodes.len() == MAX_ENTRIES {
+                truncated = true;
+                break;
+            }
+
+            let mut node_truncated = false;
+            let children = if entry.is_dir && depth > 1 && !entry.is_symlink && !is_ignored {
+                match read(&path) {
+                    Ok(sub) => {
+                        let (children, cut) = self.build(&path, sub, depth - 1, read);
+                        node_truncated = cut;
+                        Some(children)
+                    }
+                    Err(_) => None,         // Unreadable: leave it to load on expand
+                }
+            } else {
+                None
+            };
+            let path = path.to_string_lossy().to_string();
+            nodes.push(FileNode {
+                id: path.clone(),
+                name: entry.name,
+                kind: if entry.is_dir { "directory" } else { "file" },
+                path,
+                children,
+                size: if entry.is_dir { None } else { entry.size },
+                last_modified: entry.modified
+                    .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+                    .map(|t| t.to_rfc3339()),
+                is_ignored,
+                truncated: node_truncated,
+            });
+        }
+        (nodes, truncated)
+    }
+}
+
+pub fn read_local(dir: &Path) -> Result<Vec<Entry>, String> {
+    let mut entries = Vec::new();
+    for item in std::fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))? {
+        let Ok(item) = item else { continue };
+        let Ok(file_type) = item.file_type() else { continue };
+        let is_symlink = file_type.is_symlink();
+        // Follow links for type, size and mtime; dangling ones stay files
+        let meta = std::fs::metadata(item.path()).or_else(|_| item.metadata()).ok();
+        entries.push(Entry {
+            name: item.file_name().to_string_lossy().to_string(),
+            is_dir: meta.as_ref().map_or(file_type.is_dir(), |m| m.is_dir()),
+            is_symlink,
+            size: meta.as_ref().map(|m| m.len()),
+            modified: meta.and_then(|m| m.modified().ok())
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs() as i64),
+        });
+    }
+    Ok(entries)
+}
+
+pub fn read_sftp(sftp: &ssh2::Sftp, dir: &Path) -> Result<Vec<Entry>, String> {
+    let listing = sftp.readdir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    Ok(listing.into_iter().filter_map(|(path, stat)| {
+        let name = path.file_name()?.to_string_lossy().to_string();
+        let is_symlink = stat.file_type().is_symlink();
+        let stat = if is_symlink { sftp.stat(&path).unwrap_or(stat) } else { stat };
+        Some(Entry {
+            name,
+            is_dir: stat.is_dir(),
+            is_symlink,
+            size: stat.size,
+            modified: stat.mtime.map(|t| t as i64),
+        })
+    }).collect())
+}
+
+/// `.gitignore` rules apply from the current project when `path` is inside
+/// it, and from `path` itself otherwise
+async fn ignore_root(path: &Path, state: &NexusState) -> PathBuf {
+    state.current_project.lock().await
+        .clone()
+        .filter(|project| path.starts_with(project))
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+fn list_sftp(sess: &Session, path: &Path, root: &Path, depth: u32, include_ignored: bool) -> Result<Vec<FileNode>, String> {
+    let sftp = sess.sftp().map_err(|e| format!("SFTP unavailable: {}", e))?;
+    let walk = Walk { root, ignore: Gitignore::load_sftp(sess, root), include_ignored };
+    let entries = read_sftp(&sftp, path)?;
+    Ok(walk.build(path, entries, depth, &mut |dir| read_sftp(&sftp, dir)).0)
+}
+
+fn list_local(path: &Path, root: &Path, depth: u32, include_ignored: bool) -> Result<Vec<FileNode>, String> {
+    let walk = Walk { root, ignore: Gitignore::load_local(root), include_ignored };
+    let entries = read_local(path)?;
+    Ok(walk.build(path, entries, depth, &mut read_local).0)
+}
+
+/// List the children of `path` on the active host, `depth` levels deep
+/// (default 1). Directories below that come back without `children` and are
+/// listed again when expanded. Ignored entries are left out unless
+/// `include_ignored`, in which case they are flagged and not descended into.
+#[tauri::command]
+pub async fn list_directory(
+    path: String,
+    depth: Option<u32>,
+    include_ignored: Option<bool>,
+    state: State<'_, NexusState>,
+) -> Result<Vec<FileNode>, String> {
+    let path = PathBuf::from(path);
+    check_allowed(&path, &state).await?;
+    let depth = depth.unwrap_or(1).clamp(1, MAX_DEPTH);
+    let include_ignored = include_ignored.unwrap_or(false);
+    let root = ignore_root(&path, &state).await;
+
+    // A deep walk takes many round trips, so it gets its own session rather
+    // than holding up every other command on the shared one
+    let creds = state.ssh_credentials.lock().await.clone();
+    tokio::task::spawn_blocking(move || match creds {
+        Some(creds) => list_sftp(&establish_ssh(&creds)?, &path, &root, depth, include_ignored),
+        None => list_local(&path, &root, depth, include_ignored),
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// ============================================================================
+// Reading and writing
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ByteRange {
+    pub offset: u64,
+    pub length: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileContent {
+    pub path: String,
+    pub content: Option<String>,        // None for binary files
+    pub is_binary: bool,
+    pub size: u64,                      // Whole file, not just the range read
+    pub mtime: Option<i64>,             // Unix seconds; pass back to write_file
+    pub hash: Option<String>,           // SHA-256 of the whole file when it was read whole; pass back to write_file
+    pub offset: u64,
+    pub length: u64,                    // Bytes actually returned
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WrittenFile {
+    pub path: String,
+    pub size: u64,
+    pub mtime: Option<i64>,
+    pub hash: String,                   // Of the contents written
+}
+
+/// What the caller last saw of a file, to detect changes made since
+#[derive(Debug, Clone, Copy)]
+struct Expected<'a> {
+    mtime: Option<i64>,
+    hash: Option<&'a str>,
+}
+
+fn hash_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The parts of a stat that reads and writes care about
+struct Stat {
+    is_dir: bool,
+    size: u64,
+    mtime: Option<i64>,
+    perm: Option<u32>,
+}
+
+fn stat_local(path: &Path) -> Option<Stat> {
+    let meta = std::fs::metadata(path).ok()?;
+    #[cfg(unix)]
+    let perm = {
+        use std::os::unix::fs::PermissionsExt;
+        Some(meta.permissions().mode())
+    };
+    #[cfg(not(unix))]
+    let perm = None;
+    Some(Stat {
+        is_dir: meta.is_dir(),
+        size: meta.len(),
+        mtime: meta.modified().ok()
+            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64),
+        perm,
+    })
+}
+
+fn stat_sftp(sftp: &ssh2::Sftp, path: &Path) -> Option<Stat> {
+    let stat = sftp.stat(path).ok()?;
+    Some(Stat {
+        is_dir: stat.is_dir(),
+        size: stat.size.unwrap_or(0),
+        mtime: stat.mtime.map(|t| t as i64),
+        perm: stat.perm,
+    })
+}
+
+/// The span of a file to read: the requested range, or the whole file if
+/// it is small enough
+fn read_span(path: &Path, stat: &Stat, range: Option<ByteRange>) -> Result<(u64, u64), String> {
+    if stat.is_dir {
+        return Err(format!("{} is a directory", path.display()));
+    }
+    match range {
+        Some(r) => Ok((r.offset.min(stat.size), r.length.min(MAX_READ_BYTES).min(stat.size.saturating_sub(r.offset)))),
+        None if stat.size > MAX_READ_BYTES => Err(format!(
+            "{} is {:.1} MB, over the {} MB limit; read it in ranges",
+            path.display(),
+            stat.size as f64 / (1024.0 * 1024.0),
+            MAX_READ_BYTES / (1024 * 1024),
+        )),
+        None => Ok((0, stat.size)),
+    }
+}
+
+/// Decode what was read, treating NUL bytes or invalid UTF-8 as binary
+fn decode(path: &Path, stat: &Stat, offset: u64, mut bytes: Vec<u8>) -> FileContent {
+    let sniff = &bytes[..bytes.len().min(BINARY_SNIFF_BYTES)];
+    let is_binary = sniff.contains(&0) || match std::str::from_utf8(&bytes) {
+        Ok(_) => false,
+        // A character cut in half where the range ends
+        Err(e) if e.error_len().is_none() && offset + (bytes.len() as u64) < stat.size => {
+            bytes.truncate(e.valid_up_to());
+            false
+        }
+        Err(_) => true,
+    };
+    let whole = offset == 0 && bytes.len() as u64 == stat.size;
+    let hash = whole.then(|| hash_hex(&bytes));
+    let content = if is_binary { None } else { String::from_utf8(bytes).ok() };
+    FileContent {
+        path: path.to_string_lossy().to_string(),
+        hash,
+        length: content.as_ref().map_or(0, |c| c.len() as u64),
+        is_binary,
+        content,
+        size: stat.size,
+        mtime: stat.mtime,
+        offset,
+    }
+}
+
+fn read_bytes(file: &mut (impl Read + Seek), offset: u64, length: u64) -> Result<Vec<u8>, String> {
+    file.seek(SeekFrom::Start(offset)).map_err(|e| e.to_string())?;
+    let mut bytes = Vec::with_capacity(length as usize);
+    file.take(length).read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+/// Refuse the write if the file changed since it was read. The mtime has
+/// only one-second resolution, so the contents are compared too when the
+/// caller has their hash; `read` is only called in that case.
+fn check_unchanged(
+    path: &Path,
+    current: Option<&Stat>,
+    expected: Expected,
+    read: impl FnOnce() -> Result<Vec<u8>, String>,
+) -> Result<(), String> {
+    let Some(stat) = current else {
+        if expected.mtime.is_some() || expected.hash.is_some() {
+            return Err(format!("{} was deleted since it was opened", path.display()));
+        }
+        return Ok(());
+    };
+    if stat.is_dir {
+        return Err(format!("{} is a directory", path.display()));
+    }
+    let changed = expected.mtime.is_some_and(|mtime| stat.mtime != Some(mtime))
+        || match expected.hash {
+            Some(hash) => hash_hex(&read()?) != hash,
+            None => false,
+        };
+    if changed {
+        return Err(format!("{} was modified on disk since it was opened; reload it before saving", path.display()));
+    }
+    Ok(())
+}
+
+/// Sibling path the new contents are written to before the rename
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let tag: String = uuid::Uuid::new_v4().to_string().chars().take(8).collect();
+    path.with_file_name(format!(".{}.nexus-{}.tmp", name, tag))
+}
+
+/// Where a write to `path` should land: the file a symlink points to, so
+/// the rename replaces the file rather than the link
+fn link_target(path: &Path, is_symlink: bool, resolve: impl FnOnce() -> Option<PathBuf>, read_link: impl FnOnce() -> Option<PathBuf>) -> PathBuf {
+    if !is_symlink {
+        return path.to_path_buf();
+    }
+    // A dangling link can't be resolved; follow its one hop instead
+    resolve()
+        .or_else(|| read_link().map(|target| path.parent().map_or(target.clone(), |dir| dir.join(&target))))
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+fn write_local(path: &Path, content: &[u8], expected: Expected) -> Result<WrittenFile, String> {
+    let is_symlink = std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink());
+    let target = link_target(path, is_symlink, || std::fs::canonicalize(path).ok(), || std::fs::read_link(path).ok());
+    let current = stat_local(&target);
+    check_unchanged(path, current.as_ref(), expected, || std::fs::read(&target).map_err(|e| e.to_string()))?;
+
+    let temp = temp_path(&target);
+    let result = (|| {
+        std::fs::write(&temp, content)?;
+        #[cfg(unix)]
+        if let Some(perm) = current.as_ref().and_then(|s| s.perm) {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&temp, std::fs::Permissions::from_mode(perm))?;
+        }
+        std::fs::rename(&temp, &target)
+    })();
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temp);
+        return Err(format!("Failed to write {}: {}", path.display(), e));
+    }
+
+    let stat = stat_local(&target).ok_or_else(|| format!("{} vanished after writing", path.display()))?;
+    Ok(WrittenFile { path: path.to_string_lossy().to_string(), size: stat.size, mtime: stat.mtime, hash: hash_hex(content) })
+}
+
+/// Rename `from` over `to` on the SSH host. SFTP's rename won't replace an
+/// existing file on OpenSSH, so this is a `mv` on the host, which is an
+/// atomic rename(2): `to` is never missing, even if the move fails.
+pub fn replace_sftp(sess: &Session, from: &Path, to: &Path) -> Result<(), String> {
+    let mut channel = sess.channel_session().map_err(|e| e.to_string())?;
+    let command = format!("mv -f -- {} {}", shell_quote(&from.to_string_lossy()), shell_quote(&to.to_string_lossy()));
+    channel.exec(&command).map_err(|e| e.to_string())?;
+    let mut stderr = String::new();
+    let _ = channel.stderr().read_to_string(&mut stderr);
+    channel.wait_close().ok();
+    match channel.exit_status() {
+        Ok(0) => Ok(()),
+        _ => Err(stderr.trim().to_string()),
+    }
+}
+
+fn write_sftp(sess: &Session, path: &Path, content: &[u8], expected: Expected) -> Result<WrittenFile, String> {
+    let sftp = sess.sftp().map_err(|e| format!("SFTP unavailable: {}", e))?;
+    let is_symlink = sftp.lstat(path).is_ok_and(|s| s.file_type().is_symlink());
+    let target = link_target(path, is_symlink, || sftp.realpath(path).ok(), || sftp.readlink(path).ok());
+    let current = stat_sftp(&sftp, &target);
+    check_unchanged(path, current.as_ref(), expected, || {
+        let mut file = sftp.open(&target).map_err(|e| e.to_string())?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+        Ok(bytes)
+    })?;
+
+    let temp = temp_path(&target);
+    let mode = current.as_ref().and_then(|s| s.perm).map_or(0o644, |p| p & 0o7777);
+    let result = (|| {
+        let mut file = sftp.create(&temp).map_err(|e| e.to_string())?;
+        file.write_all(content).map_err(|e| e.to_string())?;
+        drop(file);
+        sftp.setstat(&temp, ssh2::FileStat {
+            size: None,
+            uid: None,
+            gid: None,
+            perm: Some(mode),
+            atime: None,
+            mtime: None,
+        }).map_err(|e| e.to_string())?;
+        replace_sftp(sess, &temp, &target)
+    })();
+    if let Err(e) = result {
+        let _ = sftp.unlink(&temp);
+        return Err(format!("Failed to write {}: {}", path.display(), e));
+    }
+
+    let stat = stat_sftp(&sftp, &target).ok_or_else(|| format!("{} vanished after writing", path.display()))?;
+    Ok(WrittenFile { path: path.to_string_lossy().to_string(), size: stat.size, mtime: stat.mtime, hash: hash_hex(content) })
+}
+
+/// Read a file on the active host, whole or just `range`. Binary files come
+/// back with `isBinary` set and no content.
+#[tauri::command]
+pub async fn read_file(path: String, range: Option<ByteRange>, state: State<'_, NexusState>) -> Result<FileContent, String> {
+    let path = PathBuf::from(path);
+    check_allowed(&path, &state).await?;
+
+    let remote = with_ssh_session(&state, |sess| {
+        let sftp = sess.sftp().map_err(|e| format!("SFTP unavailable: {}", e))?;
+        let stat = stat_sftp(&sftp, &path).ok_or_else(|| format!("{} not found", path.display()))?;
+        let (offset, length) = read_span(&path, &stat, range)?;
+        let mut file = sftp.open(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let bytes = read_bytes(&mut file, offset, length)?;
+        Ok(decode(&path, &stat, offset, bytes))
+    }).await;
+    if let Some(result) = remote {
+        return result;
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let stat = stat_local(&path).ok_or_else(|| format!("{} not found", path.display()))?;
+        let (offset, length) = read_span(&path, &stat, range)?;
+        let mut file = std::fs::File::open(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let bytes = read_bytes(&mut file, offset, length)?;
+        Ok(decode(&path, &stat, offset, bytes))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Replace a file's contents on the active host. The new contents go to a
+/// temporary sibling that is renamed over the original, so readers never see
+/// a half-written file; a symlink is written through, not replaced. With
+/// `expected_mtime` or `expected_hash` (from `read_file`) the write is
+/// refused if the file changed or disappeared in the meantime.
+#[tauri::command]
+pub async fn write_file(
+    path: String,
+    content: String,
+    expected_mtime: Option<i64>,
+    expected_hash: Option<String>,
+    state: State<'_, NexusState>,
+) -> Result<WrittenFile, String> {
+    if content.len() > MAX_WRITE_BYTES {
+        return Err(format!("Refusing to write more than {} MB", MAX_WRITE_BYTES / (1024 * 1024)));
+    }
+    let path = PathBuf::from(path);
+    check_allowed(&path, &state).await?;
+
+    let expected = Expected { mtime: expected_mtime, hash: expected_hash.as_deref() };
+    if let Some(result) = with_ssh_session(&state, |sess| write_sftp(sess, &path, content.as_bytes(), expected)).await {
+        return result;
+    }
+    tokio::task::spawn_blocking(move || {
+        let expected = Expected { mtime: expected_mtime, hash: expected_hash.as_deref() };
+        write_local(&path, content.as_bytes(), expected)
+    })
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nexus-files-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn expect(path: &Path) -> (Option<i64>, String) {
+        let stat = stat_local(path).unwrap();
+        (stat.mtime, hash_hex(&std::fs::read(path).unwrap()))
+    }
+
+    fn check(path: &Path, mtime: Option<i64>, hash: Option<&str>) -> Result<(), String> {
+        let current = stat_local(path);
+        check_unchanged(path, current.as_ref(), Expected { mtime, hash }, || std::fs::read(path).map_err(|e| e.to_string()))
+    }
+
+    #[test]
+    fn same_second_edits_are_caught_by_the_hash() {
+        let dir = scratch_dir();
+        let file = dir.join("a.txt");
+        std::fs::write(&file, "one").unwrap();
+        let (mtime, hash) = expect(&file);
+        assert_eq!(check(&file, mtime, Some(&hash)), Ok(()));
+
+        // Same size, and the same mtime to the second
+        std::fs::write(&file, "two").unwrap();
+        let seconds = mtime.unwrap();
+        filetime::set_file_mtime(&file, filetime::FileTime::from_unix_time(seconds, 0)).unwrap();
+        assert_eq!(check(&file, mtime, None), Ok(()));
+        assert!(check(&file, mtime, Some(&hash)).unwrap_err().contains("modified on disk"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn deleted_files_and_directories_are_refused() {
+        let dir = scratch_dir();
+        let file = dir.join("gone.txt");
+        assert_eq!(check(&file, None, None), Ok(()));
+        assert!(check(&file, Some(1), None).unwrap_err().contains("deleted"));
+        assert!(check(&file, None, Some("abc")).unwrap_err().contains("deleted"));
+        assert!(check(&dir, None, None).unwrap_err().contains("is a directory"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn writes_go_through_symlinks() {
+        use std::os::unix::fs::symlink;
+        let dir = scratch_dir();
+        let (link, target) = (dir.join("link.txt"), dir.join("real.txt"));
+        std::fs::write(&target, "old").unwrap();
+        symlink("real.txt", &link).unwrap();
+
+        let (mtime, hash) = expect(&link);
+        write_local(&link, b"new", Expected { mtime, hash: Some(&hash) }).unwrap();
+        assert!(std::fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "new");
+
+        // A dangling link is followed its one hop and creates the file
+        let (dangling, missing) = (dir.join("dangling.txt"), dir.join("missing.txt"));
+        symlink("missing.txt", &dangling).unwrap();
+        assert_eq!(link_target(&dangling, true, || None, || std::fs::read_link(&dangling).ok()), missing);
+        write_local(&dangling, b"created", Expected { mtime: None, hash: None }).unwrap();
+        assert_eq!(std::fs::read_to_string(&missing).unwrap(), "created");
+
+        assert_eq!(link_target(&target, false, || unreachable!(), || unreachable!()), target);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,893 @@
+// Git - status, diffs, history and branches of the current project, plus
+// staging, commits and branch switches, run on the active host
+
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{Emitter, Manager, State};
+
+use crate::projects::check_allowed;
+use crate::usage::UsageRecord;
+use crate::{
+    enforce_budget, execute_nexus_bridge, execute_shell_checked, measure_usage, parse_chat_response, record_usage,
+    shell_quote, NexusState,
+};
+
+/// How often the current branch is checked for `nexus://git-branch-changed`
+const BRANCH_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Most diff text sent when asking for a commit message
+const COMMIT_DIFF_CHARS: usize = 12_000;
+
+/// Staged files whose contents are left out of the commit message prompt
+const NOISY_FILES: &[&str] = &[
+    "Cargo.lock", "package-lock.json", "yarn.lock", "pnpm-lock.yaml", "poetry.lock", "go.sum", "*.min.js", "*.min.css", "*.map",
+];
+
+/// Mirrors `GitStatus` in src/types/index.ts
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStatus {
+    pub branch: String,                 // "HEAD" when detached
+    pub detached: bool,
+    pub head: Option<String>,           // None before the first commit
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    pub modified: Vec<String>,          // Changed in the working tree
+    pub staged: Vec<String>,
+    pub untracked: Vec<String>,
+    pub conflicted: Vec<String>,
+    pub files: Vec<GitFileStatus>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitFileStatus {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orig_path: Option<String>,      // Source of a rename or copy
+    pub index: char,                    // Porcelain XY codes; '.' is unchanged
+    pub worktree: char,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitDiffLine {
+    pub kind: &'static str,             // "context", "add" or "delete"
+    pub content: String,
+    pub old_line: Option<u32>,
+    pub new_line: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitDiffHunk {
+    pub header: String,
+    pub old_start: u32,
+    pub new_start: u32,
+    pub lines: Vec<GitDiffLine>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitFileDiff {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+    pub status: String,                 // added, modified, deleted or renamed
+    pub binary: bool,
+    pub additions: u32,
+    pub deletions: u32,
+    pub hunks: Vec<GitDiffHunk>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCommit {
+    pub hash: String,
+    pub short_hash: String,
+    pub author: String,
+    pub email: String,
+    pub date: String,                   // ISO 8601
+    pub subject: String,
+    pub refs: Vec<String>,
+}
+
+/// A suggested commit message, for the user to review before committing
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitSuggestion {
+    pub subject: String,
+    pub body: String,
+    pub message: String,                // Subject and body, ready for git_commit
+    pub truncated: bool,                // Part of the diff was left out of the prompt
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitBranch {
+    pub name: String,
+    pub is_current: bool,
+    pub is_remote: bool,
+    pub upstream: Option<String>,
+    pub commit: String,
+    pub subject: String,
+}
+
+pub async fn current_project(state: &NexusState) -> Result<String, String> {
+    state.current_project.lock().await
+        .as_ref()
+        .map(|p| p.to_string_lossy().to_string())
+        .ok_or_else(|| "No project selected".to_string())
+}
+
+/// Run `git <args>` in the project; `args` must already be shell-quoted.
+/// Paths in the output are left unquoted unless they hold control
+/// characters, quotes or backslashes.
+pub async fn git(project: &str, args: &str, state: &NexusState) -> Result<String, String> {
+    execute_shell_checked(&format!("cd {} && git -c core.quotePath=false {}", shell_quote(project), args), state).await
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+/// Parse `git status --porcelain=v2 --branch -z`
+fn parse_status(raw: &str) -> GitStatus {
+    let mut status = GitStatus::default();
+    let mut records = raw.split('\0').filter(|r| !r.is_empty());
+    while let Some(record) = records.next() {
+        if let Some(header) = record.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "branch.oid" if value != "(initial)" => status.head = Some(value.to_string()),
+                "branch.head" => {
+                    status.detached = value == "(detached)";
+                    status.branch = if status.detached { "HEAD".to_string() } else { value.to_string() };
+                }
+                "branch.upstream" => status.upstream = Some(value.to_string()),
+                "branch.ab" => {
+                    for part in value.split_whitespace() {
+                        if let Some(n) = part.strip_prefix('+') {
+                            status.ahead = n.parse().unwrap_or(0);
+                        } else if let Some(n) = part.strip_prefix('-') {
+                            status.behind = n.parse().unwrap_or(0);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        // Ordinary, renamed and unmerged entries differ only in how many
+        // fields precede the path
+        let (kind, rest) = record.split_at(1);
+        let rest = rest.strip_prefix(' ').unwrap_or(rest);
+        let fields = match kind {
+            "1" => 8,
+            "2" => 9,
+            "u" => 10,
+            "?" => {
+                status.untracked.push(rest.to_string());
+                continue;
+            }
+            _ => continue,
+        };
+        let parts: Vec<&str> = rest.splitn(fields, ' ').collect();
+        let (Some(xy), Some(path)) = (parts.first(), parts.last()) else { continue };
+        let mut codes = xy.chars();
+        let (index, worktree) = (codes.next().unwrap_or('.'), codes.next().unwrap_or('.'));
+        let orig_path = if kind == "2" { records.next().map(|p| p.to_string()) } else { None };
+        let path = path.to_string();
+
+        if kind == "u" {
+            status.conflicted.push(path.clone());
+        } else {
+            if index != '.' {
+                status.staged.push(path.clone());
+            }
+            if worktree != '.' {
+                status.modified.push(path.clone());
+            }
+        }
+        status.files.push(GitFileStatus { path, orig_path, index, worktree });
+    }
+    status
+}
+
+/// `-a,b +c,d` from a hunk header: the old and new start lines
+fn hunk_starts(header: &str) -> (u32, u32) {
+    let mut parts = header.trim_start_matches('@').split_whitespace();
+    let start = |part: Option<&str>| {
+        part.and_then(|p| p[1..].split(',').next())
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(0)
+    };
+    (start(parts.next()), start(parts.next()))
+}
+
+/// Undo git's C-style quoting of a path (`"a\\tb"`, `"\\303\\251"`); paths
+/// without quotes are returned as they are
+fn unquote_path(raw: &str) -> String {
+    let Some(inner) = raw.strip_prefix('"').and_then(|r| r.strip_suffix('"')) else {
+        return raw.to_string();
+    };
+    let mut bytes = Vec::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0u8; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('n') => bytes.push(b'\n'),
+            Some('t') => bytes.push(b'\t'),
+            Some('r') => bytes.push(b'\r'),
+            Some('a') => bytes.push(0x07),
+            Some('b') => bytes.push(0x08),
+            Some('f') => bytes.push(0x0c),
+            Some('v') => bytes.push(0x0b),
+            Some(d @ '0'..='7') => {
+                // Three octal digits make one byte of a UTF-8 sequence
+                let digits: String = std::iter::once(d).chain(chars.by_ref().take(2)).collect();
+                bytes.push(u8::from_str_radix(&digits, 8).unwrap_or(b'?'));
+            }
+            Some(other) => bytes.push(other as u8),
+            None => {}
+        }
+    }
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+/// Old and new paths from a `diff --git a/<old> b/<new>` header. Only a
+/// fallback: the `---`/`+++` and rename lines that follow are exact, but
+/// binary and mode-only changes have none.
+pub(crate) fn header_paths(paths: &str) -> (String, String) {
+    if paths.starts_with('"') {
+        // The first path is quoted; find its closing quote
+        let mut escaped = false;
+        let close = paths.char_indices().skip(1).find(|&(_, c)| {
+            let end = c == '"' && !escaped;
+            escaped = c == '\\' && !escaped;
+            end
+        });
+        if let Some((i, _)) = close {
+            let old = unquote_path(&paths[..=i]);
+            let new = unquote_path(paths[i + 1..].trim_start());
+            return (strip_side(&old, "a/"), strip_side(&new, "b/"));
+        }
+    }
+    // Unchanged paths make the header symmetric, even when they contain " b/"
+    let half = paths.len().saturating_sub(1) / 2;
+    if paths.len() % 2 == 1 && paths.is_char_boundary(half) && paths[half..].starts_with(" b/") {
+        let (old, new) = (&paths[..half], &paths[half + 1..]);
+        if old.get(2..) == new.get(2..) {
+            return (strip_side(old, "a/"), strip_side(new, "b/"));
+        }
+    }
+    match paths.split_once(" b/") {
+        Some((old, new)) => (strip_side(old, "a/"), new.to_string()),
+        None => (strip_side(paths, "a/"), strip_side(paths, "a/")),
+    }
+}
+
+fn strip_side(path: &str, prefix: &str) -> String {
+    path.strip_prefix(prefix).unwrap_or(path).to_string()
+}
+
+/// Path from a `---`/`+++` line. git ends the line with a tab when an
+/// unquoted path contains a space.
+pub(crate) fn marker_path(raw: &str, prefix: &str) -> String {
+    strip_side(&unquote_path(raw.strip_suffix('\t').unwrap_or(raw)), prefix)
+}
+
+/// Parse unified `git diff` output into files, hunks and numbered lines
+fn parse_diff(raw: &str) -> Vec<GitFileDiff> {
+    let mut files: Vec<GitFileDiff> = Vec::new();
+    let (mut old_line, mut new_line) = (0, 0);
+    for line in raw.lines() {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            let (old, new) = header_paths(paths);
+            files.push(GitFileDiff {
+                old_path: (old != new).then(|| old.clone()),
+                status: if old != new { "renamed" } else { "modified" }.to_string(),
+                path: new,
+                binary: false,
+                additions: 0,
+                deletions: 0,
+                hunks: Vec::new(),
+            });
+            continue;
+        }
+        let Some(file) = files.last_mut() else { continue };
+
+        if line.starts_with("@@") {
+            let (old_start, new_start) = hunk_starts(line);
+            (old_line, new_line) = (old_start, new_start);
+            file.hunks.push(GitDiffHunk { header: line.to_string(), old_start, new_start, lines: Vec::new() });
+            continue;
+        }
+        let Some(hunk) = file.hunks.last_mut() else {
+            // Extended headers, before the first hunk
+            if line.starts_with("new file mode") {
+                file.status = "added".to_string();
+            } else if line.starts_with("deleted file mode") {
+                file.status = "deleted".to_string();
+            } else if line.starts_with("Binary files") || line == "GIT binary patch" {
+                file.binary = true;
+            } else if let Some(from) = line.strip_prefix("rename from ") {
+                file.old_path = Some(unquote_path(from));
+                file.status = "renamed".to_string();
+            } else if let Some(to) = line.strip_prefix("rename to ") {
+                file.path = unquote_path(to);
+            } else if let Some(old) = line.strip_prefix("--- ") {
+                if old != "/dev/null" && file.status != "renamed" {
+                    file.path = marker_path(old, "a/");
+                }
+            } else if let Some(new) = line.strip_prefix("+++ ") {
+                if new != "/dev/null" {
+                    file.path = marker_path(new, "b/");
+                }
+            }
+            continue;
+        };
+        let (kind, content) = match line.chars().next() {
+            Some('+') => ("add", &line[1..]),
+            Some('-') => ("delete", &line[1..]),
+            Some(' ') => ("context", &line[1..]),
+            _ => continue,                  // "\ No newline at end of file"
+        };
+        let (old, new) = match kind {
+            "add" => {
+                file.additions += 1;
+                new_line += 1;
+                (None, Some(new_line - 1))
+            }
+            "delete" => {
+                file.deletions += 1;
+                old_line += 1;
+                (Some(old_line - 1), None)
+            }
+            _ => {
+                old_line += 1;
+                new_line += 1;
+                (Some(old_line - 1), Some(new_line - 1))
+            }
+        };
+        hunk.lines.push(GitDiffLine { kind, content: content.to_string(), old_line: old, new_line: new });
+    }
+    files
+}
+
+// ============================================================================
+// Branch changes
+// ============================================================================
+
+/// Remember the project's branch, emitting `nexus://git-branch-changed` when
+/// it differs from what was last seen
+pub async fn note_branch(app: &tauri::AppHandle, project: &str, branch: &str) {
+    let state = app.state::<NexusState>();
+    let mut last = state.git_branch.lock().await;
+    let previous = last.as_ref()
+        .filter(|(p, _)| p == project)
+        .map(|(_, b)| b.clone());
+    if previous.as_deref() == Some(branch) {
+        return;
+    }
+    *last = Some((project.to_string(), branch.to_string()));
+    let _ = app.emit("nexus://git-branch-changed", serde_json::json!({
+        "project": project,
+        "branch": branch,
+        "previous": previous,
+    }));
+}
+
+/// Poll the current project's branch so checkouts made outside the app
+/// show up too
+pub async fn watch_branch(app: tauri::AppHandle) {
+    loop {
+        tokio::time::sleep(BRANCH_POLL_INTERVAL).await;
+        let state = app.state::<NexusState>();
+        let Ok(project) = current_project(&state).await else { continue };
+        let args = "symbolic-ref --short -q HEAD || echo HEAD";
+        if let Ok(branch) = git(&project, args, &state).await {
+            note_branch(&app, &project, branch.trim()).await;
+        }
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+async fn status(app: &tauri::AppHandle, project: &str, state: &NexusState) -> Result<GitStatus, String> {
+    let raw = git(project, "status --porcelain=v2 --branch -z", state).await?;
+    let status = parse_status(&raw);
+    note_branch(app, project, &status.branch).await;
+    Ok(status)
+}
+
+async fn log(project: &str, limit: u32, state: &NexusState) -> Result<Vec<GitCommit>, String> {
+    let args = format!("log -z -n {} --format=%H%x1f%h%x1f%an%x1f%ae%x1f%aI%x1f%D%x1f%s", limit);
+    let raw = match git(project, &args, state).await {
+        Ok(raw) => raw,
+        // A repository without commits has no log
+        Err(e) if e.contains("does not have any commits") => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(raw.split('\0').filter_map(|record| {
+        let fields: Vec<&str> = record.trim_start_matches('\n').split('\x1f').collect();
+        let [hash, short_hash, author, email, date, refs, subject] = fields[..] else { return None };
+        Some(GitCommit {
+            hash: hash.to_string(),
+            short_hash: short_hash.to_string(),
+            author: author.to_string(),
+            email: email.to_string(),
+            date: date.to_string(),
+            subject: subject.to_string(),
+            refs: refs.split(", ").filter(|r| !r.is_empty()).map(|r| r.to_string()).collect(),
+        })
+    }).collect())
+}
+
+#[tauri::command]
+pub async fn git_status(app: tauri::AppHandle, state: State<'_, NexusState>) -> Result<GitStatus, String> {
+    let project = current_project(&state).await?;
+    status(&app, &project, &state).await
+}
+
+/// Working tree changes, or the index with `staged`; limited to `path` if
+/// given
+#[tauri::command]
+pub async fn git_diff(path: Option<String>, staged: Option<bool>, state: State<'_, NexusState>) -> Result<Vec<GitFileDiff>, String> {
+    let project = current_project(&state).await?;
+    let mut args = String::from("diff --no-color --no-ext-diff -M");
+    if staged.unwrap_or(false) {
+        args.push_str(" --cached");
+    }
+    if let Some(path) = path {
+        check_allowed(&std::path::Path::new(&project).join(&path), &state).await?;
+        args.push_str(&format!(" -- {}", shell_quote(&path)));
+    }
+    Ok(parse_diff(&git(&project, &args, &state).await?))
+}
+
+#[tauri::command]
+pub async fn git_log(limit: Option<u32>, state: State<'_, NexusState>) -> Result<Vec<GitCommit>, String> {
+    let project = current_project(&state).await?;
+    log(&project, limit.unwrap_or(50).clamp(1, 1000), &state).await
+}
+
+#[tauri::command]
+pub async fn git_branches(state: State<'_, NexusState>) -> Result<Vec<GitBranch>, String> {
+    let project = current_project(&state).await?;
+    let args = "for-each-ref --format='%(refname)%1f%(HEAD)%1f%(upstream:short)%1f%(objectname:short)%1f%(subject)' refs/heads refs/remotes";
+    let raw = git(&project, args, &state).await?;
+    Ok(raw.lines().filter_map(|line| {
+        let fields: Vec<&str> = line.split('\x1f').collect();
+        let [refname, head, upstream, commit, subject] = fields[..] else { return None };
+        let (name, is_remote) = match refname.strip_prefix("refs/heads/") {
+            Some(name) => (name, false),
+            None => (refname.strip_prefix("refs/remotes/")?, true),
+        };
+        // Skip the symbolic origin/HEAD
+        if is_remote && name.ends_with("/HEAD") {
+            return None;
+        }
+        Some(GitBranch {
+            name: name.to_string(),
+            is_current: head == "*",
+            is_remote,
+            upstream: (!upstream.is_empty()).then(|| upstream.to_string()),
+            commit: commit.to_string(),
+            subject: subject.to_string(),
+        })
+    }).collect())
+}
+
+// ============================================================================
+// Changes
+// ============================================================================
+
+fn quote_paths(paths: &[String]) -> Result<String, String> {
+    if paths.is_empty() {
+        return Err("No paths given".into());
+    }
+    Ok(paths.iter().map(|p| shell_quote(p)).collect::<Vec<_>>().join(" "))
+}
+
+/// Check a branch name with git's own rules; also keeps names that look
+/// like options out of the command line
+async fn check_branch_name(project: &str, name: &str, state: &NexusState) -> Result<(), String> {
+    if name.starts_with('-') {
+        return Err(format!("Invalid branch name: {}", name));
+    }
+    git(project, &format!("check-ref-format --branch {}", shell_quote(name)), state).await
+        .map(|_| ())
+        .map_err(|_| format!("Invalid branch name: {}", name))
+}
+
+/// Stage `paths`, including deletions
+#[tauri::command]
+pub async fn git_stage(paths: Vec<String>, app: tauri::AppHandle, state: State<'_, NexusState>) -> Result<GitStatus, String> {
+    let project = current_project(&state).await?;
+    git(&project, &format!("add -A -- {}", quote_paths(&paths)?), &state).await?;
+    status(&app, &project, &state).await
+}
+
+/// Take `paths` out of the index, leaving the working tree alone
+#[tauri::command]
+pub async fn git_unstage(paths: Vec<String>, app: tauri::AppHandle, state: State<'_, NexusState>) -> Result<GitStatus, String> {
+    let project = current_project(&state).await?;
+    let paths = quote_paths(&paths)?;
+    // Before the first commit there is no HEAD to reset to
+    let script = format!(
+        "cd {project} && if git rev-parse -q --verify HEAD >/dev/null; then git reset -q -- {paths}; else git rm -q -r --cached -- {paths}; fi",
+        project = shell_quote(&project),
+        paths = paths,
+    );
+    execute_shell_checked(&script, &state).await?;
+    status(&app, &project, &state).await
+}
+
+/// Commit the index. Refuses with unresolved conflicts, with nothing staged,
+/// on a detached HEAD unless `allow_detached`, and when amending a commit
+/// that is already on the upstream. Hooks run as usual and a failing hook
+/// stops the commit.
+#[tauri::command]
+pub async fn git_commit(
+    message: String,
+    amend: Option<bool>,
+    allow_detached: Option<bool>,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<GitCommit, String> {
+    let message = message.trim();
+    if message.is_empty() {
+        return Err("Commit message is empty".into());
+    }
+    let amend = amend.unwrap_or(false);
+    let project = current_project(&state).await?;
+    let current = status(&app, &project, &state).await?;
+
+    if !current.conflicted.is_empty() {
+        return Err(format!("Resolve conflicts first: {}", current.conflicted.join(", ")));
+    }
+    if current.detached && !allow_detached.unwrap_or(false) {
+        return Err("HEAD is detached, so the commit would not be on any branch. Confirm to commit anyway.".into());
+    }
+    if amend {
+        if current.head.is_none() {
+            return Err("There is no commit to amend".into());
+        }
+        if let Some(upstream) = current.upstream.as_ref().filter(|_| current.ahead == 0) {
+            return Err(format!("The last commit is already on {}; amending it would rewrite published history", upstream));
+        }
+    } else if current.staged.is_empty() {
+        return Err("Nothing staged to commit".into());
+    }
+
+    let mut args = format!("commit -q -m {}", shell_quote(message));
+    if amend {
+        args.push_str(" --amend");
+    }
+    git(&project, &args, &state).await
+        .map_err(|e| format!("Commit failed: {}", e))?;
+    eprintln!("[Tauri] Committed to {} in {}", current.branch, project);
+    log(&project, 1, &state).await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "git did not report the new commit".to_string())
+}
+
+/// Switch to an existing branch. A remote branch name without a local
+/// branch creates a tracking one. Local changes that the switch would
+/// overwrite make git refuse. Uses `git switch`, since `git checkout`
+/// falls back to restoring files when no branch has the name.
+#[tauri::command]
+pub async fn git_checkout_branch(name: String, app: tauri::AppHandle, state: State<'_, NexusState>) -> Result<GitStatus, String> {
+    let project = current_project(&state).await?;
+    check_branch_name(&project, &name, &state).await?;
+    let current = status(&app, &project, &state).await?;
+    if !current.conflicted.is_empty() {
+        return Err("Resolve conflicts before switching branches".into());
+    }
+    git(&project, &format!("switch -q {}", shell_quote(&name)), &state).await?;
+    status(&app, &project, &state).await
+}
+
+/// Create a branch at `start_point` (HEAD by default) and switch to it
+/// unless `checkout` is false
+#[tauri::command]
+pub async fn git_create_branch(
+    name: String,
+    start_point: Option<String>,
+    checkout: Option<bool>,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<GitStatus, String> {
+    let project = current_project(&state).await?;
+    check_branch_name(&project, &name, &state).await?;
+    let mut args = if checkout.unwrap_or(true) {
+        format!("checkout -q -b {}", shell_quote(&name))
+    } else {
+        format!("branch {}", shell_quote(&name))
+    };
+    if let Some(start) = start_point {
+        if start.starts_with('-') {
+            return Err(format!("Invalid start point: {}", start));
+        }
+        args.push_str(&format!(" {}", shell_quote(&start)));
+    }
+    git(&project, &args, &state).await?;
+    status(&app, &project, &state).await
+}
+
+// ============================================================================
+// Commit messages
+// ============================================================================
+
+/// Cut each file's section of `diff` to fit `budget` overall. Small files
+/// are kept whole and whatever they leave over is shared among the bigger
+/// ones; lockfiles and minified files only keep their header. Returns the
+/// diff and whether anything was cut.
+fn condense_diff(diff: &str, budget: usize) -> (String, bool) {
+    let mut sections: Vec<(String, String)> = Vec::new();
+    for line in diff.lines() {
+        if line.starts_with("diff --git ") || sections.is_empty() {
+            let path = line.strip_prefix("diff --git ").map(|h| header_paths(h).1).unwrap_or_default();
+            sections.push((path, String::new()));
+        }
+        if let Some((_, text)) = sections.last_mut() {
+            text.push_str(line);
+            text.push('\n');
+        }
+    }
+
+    let mut truncated = false;
+    for (path, text) in sections.iter_mut() {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        if NOISY_FILES.iter().any(|p| crate::gitignore::glob_match(p.as_bytes(), name.as_bytes())) {
+            let header = text.lines().next().unwrap_or("").to_string();
+            *text = format!("{}\n[contents omitted]\n", header);
+            truncated = true;
+        }
+    }
+
+    // Hand out the budget smallest first, each getting an equal share of
+    // what is left
+    let mut order: Vec<usize> = (0..sections.len()).collect();
+    order.sort_by_key(|&i| sections[i].1.len());
+    let mut remaining = budget;
+    let mut allowance = vec![0; sections.len()];
+    for (n, &i) in order.iter().enumerate() {
+        let share = remaining / (order.len() - n);
+        allowance[i] = sections[i].1.len().min(share);
+        remaining -= allowance[i];
+    }
+
+    let mut out = String::new();
+    for ((_, text), limit) in sections.iter().zip(allowance) {
+        if text.len() <= limit {
+            out.push_str(text);
+            continue;
+        }
+        truncated = true;
+        let mut kept = 0;
+        for line in text.lines() {
+            if kept + line.len() + 1 > limit {
+                break;
+            }
+            out.push_str(line);
+            out.push('\n');
+            kept += line.len() + 1;
+        }
+        let omitted = text[kept..].lines().count();
+        out.push_str(&format!("[... {} more lines]\n", omitted));
+    }
+    (out, truncated)
+}
+
+fn commit_prompt(stat: &str, diff: &str) -> String {
+    format!(
+        "Write a git commit message for the staged changes below, in the Conventional Commits format.\n\
+         - First line: <type>(<optional scope>): <summary>, in the imperative mood and at most 72 characters. \
+         Use one of feat, fix, refactor, perf, docs, test, build, ci, chore or style.\n\
+         - Then a blank line and a short body saying what changed and why, wrapped at 72 columns. \
+         Leave the body out for trivial changes.\n\
+         - Reply with the commit message only: no code fences, quotes or commentary.\n\n\
+         Files changed:\n{}\n\
+         Diff:\n{}",
+        stat.trim_end(),
+        diff,
+    )
+}
+
+/// Split the model's reply into subject and body, dropping any code fence
+/// or quotes it added anyway
+fn parse_suggestion(reply: &str, truncated: bool) -> Result<CommitSuggestion, String> {
+    let lines: Vec<&str> = reply.lines()
+        .filter(|l| !l.trim_start().starts_with("```"))
+        .collect();
+    let text = lines.join("\n");
+    let text = text.trim();
+    let (subject, body) = text.split_once('\n').unwrap_or((text, ""));
+    let subject = subject.trim().trim_matches(|c| c == '"' || c == '`').trim().to_string();
+    if subject.is_empty() {
+        return Err("The model returned an empty commit message".into());
+    }
+    let body = body.trim().to_string();
+    let message = if body.is_empty() { subject.clone() } else { format!("{}\n\n{}", subject, body) };
+    Ok(CommitSuggestion { subject, body, message, truncated })
+}
+
+/// Suggest a commit message for the staged changes in the current project.
+/// The diff is condensed to fit the prompt and sent through the chat bridge;
+/// nothing is committed.
+#[tauri::command]
+pub async fn generate_commit_message(
+    override_budget: Option<bool>,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<CommitSuggestion, String> {
+    let project = current_project(&state).await?;
+    let stat = git(&project, "diff --cached --stat --no-color", &state).await?;
+    if stat.trim().is_empty() {
+        return Err("Nothing staged to describe".into());
+    }
+    let raw = git(&project, "diff --cached --no-color --no-ext-diff -M", &state).await?;
+    let (diff, truncated) = condense_diff(&raw, COMMIT_DIFF_CHARS);
+    let prompt = commit_prompt(&stat, &diff);
+    enforce_budget(&prompt, None, override_budget.unwrap_or(false), &state).await?;
+
+    let start = std::time::Instant::now();
+    let response = execute_nexus_bridge(&["--json", "chat", &prompt], &state).await?;
+    let reported_failure = serde_json::from_str::<serde_json::Value>(&response)
+        .ok()
+        .is_some_and(|json| json["success"].as_bool() == Some(false));
+    let reply = parse_chat_response(&response);
+    if reported_failure {
+        return Err(reply);
+    }
+
+    let usage = measure_usage(&response, &prompt, &reply, None, start.elapsed().as_millis() as u64, &state).await;
+    record_usage(UsageRecord {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        source: "commit_message".to_string(),
+        conversation_id: None,
+        message_id: None,
+        project: Some(project),
+        usage,
+    }, &app, &state).await;
+    parse_suggestion(&reply, truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_reads_branch_renames_and_untracked() {
+        let raw = [
+            "# branch.oid 53405346e94c60650d0275f34bdc0e7d90e69013",
+            "# branch.head main",
+            "# branch.upstream origin/main",
+            "# branch.ab +2 -1",
+            "1 .M N... 100644 100644 100644 bdc955b bdc955b src/main.rs",
+            "2 R. N... 100644 100644 100644 0fdf397 e0318ee R83 docs/new name.md",
+            "docs/old name.md",
+            "u UU N... 100644 100644 100644 100644 aaaaaaa bbbbbbb ccccccc both.txt",
+            "? notes/é.txt",
+            "",
+        ].join("\0");
+        let status = parse_status(&raw);
+        assert_eq!(status.branch, "main");
+        assert!(!status.detached);
+        assert_eq!(status.upstream.as_deref(), Some("origin/main"));
+        assert_eq!((status.ahead, status.behind), (2, 1));
+        assert_eq!(status.modified, ["src/main.rs"]);
+        assert_eq!(status.staged, ["docs/new name.md"]);
+        assert_eq!(status.conflicted, ["both.txt"]);
+        assert_eq!(status.untracked, ["notes/é.txt"]);
+        let renamed = &status.files[1];
+        assert_eq!(renamed.orig_path.as_deref(), Some("docs/old name.md"));
+        assert_eq!((renamed.index, renamed.worktree), ('R', '.'));
+    }
+
+    #[test]
+    fn status_before_first_commit_and_detached() {
+        let status = parse_status("# branch.oid (initial)\0# branch.head (detached)\0");
+        assert_eq!(status.head, None);
+        assert!(status.detached);
+        assert_eq!(status.branch, "HEAD");
+    }
+
+    const DIFF: &str = r#"diff --git a/bin.dat b/bin.dat
+index bdc955b..8835708 100644
+Binary files a/bin.dat and b/bin.dat differ
+diff --git a/old.txt b/new.txt
+similarity index 83%
+rename from old.txt
+rename to new.txt
+index 0fdf397..e0318ee 100644
+--- a/old.txt
++++ b/new.txt
+@@ -3,4 +3,4 @@ b
+ c
+ d
+ e
+-f
++F
+diff --git a/nonl.txt b/nonl.txt
+index c1b0730..e25f181 100644
+--- a/nonl.txt
++++ b/nonl.txt
+@@ -1 +1 @@
+-x
+\ No newline at end of file
++y
+\ No newline at end of file
+diff --git "a/we\"ird.txt" "b/we\"ird.txt"
+new file mode 100644
+index 0000000..bca70f3
+--- /dev/null
++++ "b/we\"ird.txt"
+@@ -0,0 +1 @@
++q
+diff --git "a/\303\251.rs" "b/\303\251.rs"
+deleted file mode 100644
+index 45b983b..0000000
+--- "a/\303\251.rs"
++++ /dev/null
+@@ -1 +0,0 @@
+-hi
+diff --git a/x y.txt b/x y.txt
+index 7898192..6178079 100644
+--- a/x y.txt	
++++ b/x y.txt	
+@@ -1 +1 @@
+-a
++b
+"#;
+
+    #[test]
+    fn diff_splits_files_with_their_own_hunks() {
+        let files = parse_diff(DIFF);
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["bin.dat", "new.txt", "nonl.txt", "we\"ird.txt", "é.rs", "x y.txt"]);
+
+        assert!(files[0].binary);
+        assert!(files[0].hunks.is_empty());
+
+        let renamed = &files[1];
+        assert_eq!(renamed.status, "renamed");
+        assert_eq!(renamed.old_path.as_deref(), Some("old.txt"));
+        assert_eq!((renamed.additions, renamed.deletions), (1, 1));
+        let lines = &renamed.hunks[0].lines;
+        assert_eq!((lines[0].old_line, lines[0].new_line), (Some(3), Some(3)));
+        assert_eq!((lines[3].kind, lines[3].old_line), ("delete", Some(6)));
+        assert_eq!((lines[4].kind, lines[4].new_line), ("add", Some(6)));
+
+        // The "\ No newline" markers are not lines of the file
+        let nonl = &files[2];
+        assert_eq!(nonl.status, "modified");
+        assert_eq!(nonl.hunks[0].lines.len(), 2);
+
+        assert_eq!(files[3].status, "added");
+        assert_eq!(files[3].hunks[0].lines[0].content, "q");
+        assert_eq!(files[4].status, "deleted");
+        assert_eq!(files[4].old_path, None);
+        assert_eq!(files[4].deletions, 1);
+    }
+
+    #[test]
+    fn header_paths_handle_spaces_and_quotes() {
+        assert_eq!(header_paths("a/x b/y.txt b/x b/y.txt"), ("x b/y.txt".to_string(), "x b/y.txt".to_string()));
+        assert_eq!(header_paths("a/old.txt b/new.txt"), ("old.txt".to_string(), "new.txt".to_string()));
+        assert_eq!(header_paths(r#""a/tab\there" "b/tab\there""#), ("tab\there".to_string(), "tab\there".to_string()));
+        assert_eq!(unquote_path(r#""\303\251\\\"""#), "é\\\"");
+        assert_eq!(unquote_path("plain.rs"), "plain.rs");
+    }
+}
@@ -0,0 +1,230 @@
+// Gitignore matching - enough of git's rules to hide ignored files from the
+// watcher, file tree and search
+
+use ssh2::Session;
+use std::io::Read;
+use std::path::Path;
+
+#[derive(Debug, Clone)]
+struct Rule {
+    pattern: String,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool,                     // Matched against the whole path, not just the name
+}
+
+/// Patterns from the project's root `.gitignore` and `.git/info/exclude`.
+/// Nested `.gitignore` files are not read.
+#[derive(Debug, Clone, Default)]
+pub struct Gitignore {
+    rules: Vec<Rule>,
+}
+
+impl Gitignore {
+    fn add(&mut self, contents: &str) {
+        for line in contents.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negated, line) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (dir_only, line) = match line.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            let anchored = line.contains('/') && !line.starts_with("**/");
+            let pattern = line.trim_start_matches('/');
+            let pattern = if anchored { pattern } else { pattern.trim_start_matches("**/") };
+            if pattern.is_empty() {
+                continue;
+            }
+            self.rules.push(Rule {
+                pattern: pattern.to_string(),
+                negated,
+                dir_only,
+                anchored,
+            });
+        }
+    }
+
+    pub fn load_local(root: &Path) -> Self {
+        let mut ignore = Self::default();
+        for file in [root.join(".gitignore"), root.join(".git/info/exclude")] {
+            if let Ok(contents) = std::fs::read_to_string(file) {
+                ignore.add(&contents);
+            }
+        }
+        ignore
+    }
+
+    pub fn load_sftp(sess: &Session, root: &Path) -> Self {
+        let mut ignore = Self::default();
+        let Ok(sftp) = sess.sftp() else {
+            return ignore;
+        };
+        for file in [root.join(".gitignore"), root.join(".git/info/exclude")] {
+            let mut contents = String::new();
+            if let Ok(mut f) = sftp.open(&file) {
+                if f.read_to_string(&mut contents).is_ok() {
+                    ignore.add(&contents);
+                }
+            }
+        }
+        ignore
+    }
+
+    /// Whether `path` (relative to the project root, `/`-separated) is
+    /// ignored, either itself or through one of its parent directories.
+    /// `.git` is always ignored.
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        let path = path.trim_start_matches("./").trim_matches('/');
+        if path.is_empty() {
+            return false;
+        }
+        let components: Vec<&str> = path.split('/').collect();
+        for end in 1..=components.len() {
+            let sub = components[..end].join("/");
+            let sub_is_dir = end < components.len() || is_dir;
+            if components[end - 1] == ".git" || self.matches(&sub, sub_is_dir) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// The last rule that matches decides, so later `!` patterns re-include
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let mut ignored = false;
+        for rule in &self.rules {
+            if rule.dir_only && !is_dir {
+                continue;
+            }
+            let subject = if rule.anchored { path } else { name };
+            if glob_match(rule.pattern.as_bytes(), subject.as_bytes()) {
+                ignored = !rule.negated;
+            }
+        }
+        ignored
+    }
+}
+
+/// Shell-style glob: `*` and `?` stop at `/`, `**` crosses directories and
+/// `[...]` matches a character class
+pub fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some(b'*') if pattern.get(1) == Some(&b'*') => {
+            let rest = pattern[2..].strip_prefix(b"/").unwrap_or(&pattern[2..]);
+            (0..=text.len()).any(|i| glob_match(rest, &text[i..]))
+        }
+        Some(b'*') => {
+            let rest = &pattern[1..];
+            for i in 0..=text.len() {
+                if glob_match(rest, &text[i..]) {
+                    return true;
+                }
+                if text.get(i) == Some(&b'/') {
+                    break;
+                }
+            }
+            false
+        }
+        Some(b'?') => matches!(text.first(), Some(c) if *c != b'/') && glob_match(&pattern[1..], &text[1..]),
+        Some(b'[') => {
+            let Some(close) = pattern.iter().skip(1).position(|c| *c == b']').map(|p| p + 1) else {
+                return text.first() == Some(&b'[') && glob_match(&pattern[1..], &text[1..]);
+            };
+            let Some(&c) = text.first() else { return false };
+            let class = &pattern[1..close];
+            let (negate, class) = match class.first() {
+                Some(b'!') | Some(b'^') => (true, &class[1..]),
+                _ => (false, class),
+            };
+            let mut found = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == b'-' {
+                    found |= class[i] <= c && c <= class[i + 2];
+                    i += 3;
+                } else {
+                    found |= class[i] == c;
+                    i += 1;
+                }
+            }
+            found != negate && glob_match(&pattern[close + 1..], &text[1..])
+        }
+        Some(b'\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && glob_match(&pattern[2..], &text[1..])
+        }
+        Some(p) => text.first() == Some(p) && glob_match(&pattern[1..], &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(contents: &str) -> Gitignore {
+        let mut ignore = Gitignore::default();
+        ignore.add(contents);
+        ignore
+    }
+
+    #[test]
+    fn star_stops_at_slash_and_double_star_crosses_it() {
+        assert!(glob_match(b"*.log", b"debug.log"));
+        assert!(!glob_match(b"*.log", b"logs/debug.log"));
+        assert!(glob_match(b"src/**/*.rs", b"src/a/b/main.rs"));
+        assert!(glob_match(b"src/**/*.rs", b"src/main.rs"));
+        assert!(glob_match(b"file?.[a-c]", b"file1.b"));
+        assert!(!glob_match(b"file?.[!a-c]", b"file1.b"));
+    }
+
+    #[test]
+    fn later_negation_reincludes() {
+        let ignore = rules("*.log\n!keep.log\n");
+        assert!(ignore.is_ignored("debug.log", false));
+        assert!(ignore.is_ignored("logs/debug.log", false));
+        assert!(!ignore.is_ignored("keep.log", false));
+        assert!(!ignore.is_ignored("logs/keep.log", false));
+    }
+
+    #[test]
+    fn leading_double_star_matches_at_any_depth() {
+        let ignore = rules("**/build\n");
+        assert!(ignore.is_ignored("build", true));
+        assert!(ignore.is_ignored("app/build/out.js", false));
+        assert!(!ignore.is_ignored("app/builder.js", false));
+    }
+
+    #[test]
+    fn dir_only_rules_skip_files() {
+        let ignore = rules("cache/\n");
+        assert!(ignore.is_ignored("cache", true));
+        assert!(ignore.is_ignored("cache/data.bin", false));
+        assert!(ignore.is_ignored("src/cache/data.bin", false));
+        assert!(!ignore.is_ignored("cache", false));
+    }
+
+    #[test]
+    fn anchored_rules_match_from_the_root() {
+        let ignore = rules("/target\ndocs/*.html\n");
+        assert!(ignore.is_ignored("target", true));
+        assert!(ignore.is_ignored("target/debug/app", false));
+        assert!(!ignore.is_ignored("crates/target", true));
+        assert!(ignore.is_ignored("docs/index.html", false));
+        assert!(!ignore.is_ignored("site/docs/index.html", false));
+    }
+
+    #[test]
+    fn git_directory_is_always_ignored() {
+        let ignore = Gitignore::default();
+        assert!(ignore.is_ignored(".git", true));
+        assert!(ignore.is_ignored(".git/HEAD", false));
+        assert!(!ignore.is_ignored("src/main.rs", false));
+    }
+}
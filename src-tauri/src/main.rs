@@ -2,16 +2,43 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tauri::State;
 use tokio::process::Command as TokioCommand;
 use tokio::sync::Mutex;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use ssh2::Session;
 use std::net::TcpStream;
 use std::io::Read;
 use tauri::Emitter;
+use tauri::Manager;
+use tauri_plugin_notification::NotificationExt;
+
+mod attachments;
+mod budget;
+mod conversation;
+mod files;
+mod git;
+mod gitignore;
+mod notifications;
+mod process;
+mod projects;
+mod search;
+mod swarm;
+mod templates;
+mod transfer;
+mod usage;
+mod watcher;
+
+use attachments::AttachmentRef;
+use budget::{Budget, BudgetBook, BudgetStatus, CostEstimate, SpendContext};
+use conversation::{BranchInfo, ChatMessageRecord, Conversation};
+use notifications::{FocusTarget, NotificationSettings, NotifyEvent};
+use projects::{ProjectEntry, ProjectRegistry};
+use swarm::{SwarmConfig, SwarmRecord};
+use templates::PromptTemplate;
+use usage::{MessageUsage, UsageBucket, UsageRecord};
 
 // ============================================================================
 // Types
@@ -32,15 +59,6 @@ struct NexusStatus {
     remote_nexus_installed: Option<bool>,   // Whether CLI exists on remote
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct ChatMessageRecord {
-    id: String,
-    role: String,
-    content: String,
-    timestamp: String,
-    is_streaming: bool,
-}
-
 #[derive(Clone, Default)]
 struct SshCredentials {
     host: String,
@@ -55,8 +73,20 @@ struct NexusState {
     ssh_session: Mutex<Option<Session>>,
     ssh_credentials: Mutex<Option<SshCredentials>>,
     current_project: Mutex<Option<PathBuf>>,
-    active_swarms: Arc<Mutex<HashMap<String, String>>>,
-    chat_history: Mutex<Vec<ChatMessageRecord>>,
+    active_swarms: Arc<Mutex<HashMap<String, SwarmRecord>>>,
+    swarm_queue: Mutex<VecDeque<String>>,
+    swarm_config: Mutex<SwarmConfig>,
+    swarm_wakeup: tokio::sync::Notify,
+    chat_history: Mutex<Conversation>,
+    usage_ledger: Mutex<Vec<UsageRecord>>,
+    budgets: Mutex<BudgetBook>,
+    notification_settings: Mutex<NotificationSettings>,
+    pending_focus: Mutex<Option<(std::time::Instant, FocusTarget)>>,
+    file_watcher: Mutex<Option<watcher::WatchHandle>>,
+    active_search: Mutex<Option<Arc<std::sync::atomic::AtomicBool>>>,
+    git_branch: Mutex<Option<(String, String)>>,     // Last seen (project, branch)
+    projects: Mutex<ProjectRegistry>,
+    cli_model: Mutex<Option<(Option<String>, Option<String>)>>,  // Provider and model from the CLI config, once read
 }
 
 impl NexusState {
@@ -66,7 +96,19 @@ impl NexusState {
             ssh_credentials: Mutex::new(None),
             current_project: Mutex::new(None),
             active_swarms: Arc::new(Mutex::new(HashMap::new())),
-            chat_history: Mutex::new(Vec::new()),
+            swarm_queue: Mutex::new(VecDeque::new()),
+            swarm_config: Mutex::new(SwarmConfig::default()),
+            swarm_wakeup: tokio::sync::Notify::new(),
+            chat_history: Mutex::new(Conversation::new()),
+            usage_ledger: Mutex::new(Vec::new()),
+            budgets: Mutex::new(BudgetBook::default()),
+            notification_settings: Mutex::new(NotificationSettings::default()),
+            pending_focus: Mutex::new(None),
+            file_watcher: Mutex::new(None),
+            active_search: Mutex::new(None),
+            git_branch: Mutex::new(None),
+            projects: Mutex::new(ProjectRegistry::default()),
+            cli_model: Mutex::new(None),
         }
     }
 }
@@ -109,11 +151,44 @@ fn establish_ssh(creds: &SshCredentials) -> Result<Session, String> {
     Ok(sess)
 }
 
+/// Run `f` against the live SSH session, reconnecting from stored credentials
+/// if it went stale. Returns `None` when no SSH connection is configured.
+async fn with_ssh_session<T>(
+    state: &NexusState,
+    f: impl FnOnce(&Session) -> Result<T, String>,
+) -> Option<Result<T, String>> {
+    let mut lock = state.ssh_session.lock().await;
+    if lock.as_ref().is_some_and(|sess| !is_session_alive(sess)) {
+        *lock = None;
+    }
+    if lock.is_none() {
+        let creds = state.ssh_credentials.lock().await.clone()?;
+        match establish_ssh(&creds) {
+            Ok(sess) => *lock = Some(sess),
+            Err(e) => return Some(Err(e)),
+        }
+    }
+    lock.as_ref().map(f)
+}
+
+/// Quote a value for safe use in a POSIX shell command line
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// The shell command line that runs `nexus` with `args` on the SSH host.
+/// Every argument is quoted, so prompts and paths reach the CLI verbatim.
+fn nexus_command(args: &[&str]) -> String {
+    let quoted: Vec<String> = args.iter().map(|a| shell_quote(a)).collect();
+    format!("nexus {}", quoted.join(" "))
+}
+
 // ============================================================================
 // Remote Execution Bridge
 // ============================================================================
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn connect_remote(
     host: String,
     port: u16,
@@ -121,6 +196,7 @@ async fn connect_remote(
     password: Option<String>,
     private_key: Option<String>,
     public_key: Option<String>,
+    app: tauri::AppHandle,
     state: State<'_, NexusState>,
 ) -> Result<(), String> {
     let creds = SshCredentials {
@@ -129,8 +205,19 @@ async fn connect_remote(
     };
 
     let sess = establish_ssh(&creds)?;
+    let host = projects::host_key(Some(&creds));
     *state.ssh_session.lock().await = Some(sess);
     *state.ssh_credentials.lock().await = Some(creds);
+    // The remote CLI has its own config
+    *state.cli_model.lock().await = None;
+
+    // Reopen the project last used on this host
+    let last = state.projects.lock().await.most_recent(host.as_deref()).map(|p| p.path.clone());
+    if let Some(path) = last {
+        if let Err(e) = projects::select(&app, &path, &state).await {
+            eprintln!("[Tauri] Not reopening {}: {}", path, e);
+        }
+    }
     Ok(())
 }
 
@@ -141,7 +228,7 @@ async fn execute_nexus_bridge(args: &[&str], state: &NexusState) -> Result<Strin
         if let Some(sess) = lock.as_ref() {
             if is_session_alive(sess) {
                 let mut channel = sess.channel_session().map_err(|e| e.to_string())?;
-                let cmd = format!("nexus {}", args.join(" "));
+                let cmd = nexus_command(args);
                 channel.exec(&cmd).map_err(|e| e.to_string())?;
                 let mut output = String::new();
                 channel.read_to_string(&mut output).map_err(|e| e.to_string())?;
@@ -156,7 +243,7 @@ async fn execute_nexus_bridge(args: &[&str], state: &NexusState) -> Result<Strin
         if let Some(ref c) = *creds {
             if let Ok(new_sess) = establish_ssh(c) {
                 let mut channel = new_sess.channel_session().map_err(|e| e.to_string())?;
-                let cmd = format!("nexus {}", args.join(" "));
+                let cmd = nexus_command(args);
                 channel.exec(&cmd).map_err(|e| e.to_string())?;
                 let mut output = String::new();
                 channel.read_to_string(&mut output).map_err(|e| e.to_string())?;
@@ -242,6 +329,32 @@ async fn execute_shell_bridge(command: &str, working_dir: Option<&str>, state: &
     Ok(stdout)
 }
 
+/// Run a shell script on the active host like `execute_shell_bridge`, but
+/// fail with its stderr when it exits non-zero
+async fn execute_shell_checked(script: &str, state: &NexusState) -> Result<String, String> {
+    let output = execute_shell_bridge(&report_exit_status(script), None, state).await?;
+    parse_exit_status(&output)
+}
+
+/// Append the script's exit status to stdout, after a record separator
+fn report_exit_status(script: &str) -> String {
+    format!("{}\nstatus=$?; printf '\\036%d' \"$status\"; exit $status", script)
+}
+
+/// Split the output of a `report_exit_status` script into stdout on
+/// success or an error message on failure
+fn parse_exit_status(output: &str) -> Result<String, String> {
+    let Some((stdout, rest)) = output.rsplit_once('\u{1e}') else {
+        return Err(output.trim().to_string());
+    };
+    let (code, stderr) = rest.split_once('\n').unwrap_or((rest, ""));
+    match code.trim() {
+        "0" => Ok(stdout.to_string()),
+        _ if !stderr.trim().is_empty() => Err(stderr.trim().to_string()),
+        code => Err(format!("Command exited with status {}", code)),
+    }
+}
+
 // ============================================================================
 // Command Handlers
 // ============================================================================
@@ -353,7 +466,9 @@ async fn get_nexus_status(state: State<'_, NexusState>) -> Result<NexusStatus, S
         ("local".to_string(), false)
     };
 
-    let (provider, model) = get_provider_and_model_from_config(&state).await;
+    // Status is polled, which keeps the cache in step with config changes
+    // made outside the app
+    let (provider, model) = refresh_provider_and_model(&state).await;
 
     Ok(NexusStatus {
         daemon_running: false,
@@ -371,7 +486,18 @@ async fn get_nexus_status(state: State<'_, NexusState>) -> Result<NexusStatus, S
     })
 }
 
+/// Provider and model the CLI is configured with, read once and then
+/// served from `NexusState::cli_model`
 async fn get_provider_and_model_from_config(state: &NexusState) -> (Option<String>, Option<String>) {
+    if let Some(cached) = state.cli_model.lock().await.clone() {
+        return cached;
+    }
+    refresh_provider_and_model(state).await
+}
+
+/// Read the provider and model from the CLI config and cache them. Nothing
+/// is cached when the CLI can't be reached.
+async fn refresh_provider_and_model(state: &NexusState) -> (Option<String>, Option<String>) {
     // Try to get config from CLI
     let config_result = execute_nexus_bridge(&["--json", "config", "get", "all"], state).await;
 
@@ -388,6 +514,7 @@ async fn get_provider_and_model_from_config(state: &NexusState) -> (Option<Strin
                     None
                 };
 
+                *state.cli_model.lock().await = Some((provider.clone(), model.clone()));
                 return (provider, model);
             }
         }
@@ -403,9 +530,15 @@ async fn scan_project(path: String, state: State<'_, NexusState>) -> Result<Stri
 }
 
 #[tauri::command]
-async fn set_current_project(path: String, state: State<'_, NexusState>) -> Result<(), String> {
-    *state.current_project.lock().await = Some(PathBuf::from(path));
-    Ok(())
+async fn set_current_project(path: String, app: tauri::AppHandle, state: State<'_, NexusState>) -> Result<ProjectEntry, String> {
+    let entry = projects::select(&app, &path, &state).await?;
+
+    // Keep a running watcher on the current project
+    let watching = state.file_watcher.lock().await.as_ref().map(|w| w.project.clone());
+    if watching.is_some_and(|project| project != entry.path) {
+        watcher::start(&app, &state, entry.path.clone()).await?;
+    }
+    Ok(entry)
 }
 
 #[tauri::command]
@@ -414,79 +547,300 @@ async fn get_current_project(state: State<'_, NexusState>) -> Result<Option<Stri
         .as_ref().map(|p| p.to_string_lossy().to_string()))
 }
 
-#[tauri::command]
-async fn start_swarm_task(task: String, state: State<'_, NexusState>) -> Result<String, String> {
-    let task_id = uuid::Uuid::new_v4().to_string();
-    state.active_swarms.lock().await.insert(task_id.clone(), task.clone());
+/// Extract the assistant text from a `nexus --json chat` response
+fn parse_chat_response(raw: &str) -> String {
+    if let Ok(json) = serde_json::from_str::<serde_json::Value>(raw) {
+        if json["success"].as_bool() == Some(true) {
+            json["data"]["response"].as_str().unwrap_or(raw).to_string()
+        } else {
+            json["error"].as_str().unwrap_or("Unknown error").to_string()
+        }
+    } else {
+        raw.to_string()
+    }
+}
 
-    // Non-interactive swarm: call nexus chat with the swarm task description
-    let output = execute_nexus_bridge(&["--json", "chat", &task], &state).await?;
+/// The error a `--json` reply carries when the CLI reports a failure
+fn reported_error(raw: &str) -> Option<String> {
+    let json = serde_json::from_str::<serde_json::Value>(raw).ok()?;
+    (json["success"].as_bool() == Some(false)).then(|| parse_chat_response(raw))
+}
 
-    Ok(serde_json::json!({
-        "task_id": task_id,
-        "output": output,
-    }).to_string())
+struct ChatReply {
+    content: String,
+    usage: MessageUsage,
 }
 
-#[tauri::command]
-async fn get_swarm_status(id: String, state: State<'_, NexusState>) -> Result<String, String> {
-    let swarms = state.active_swarms.lock().await;
-    match swarms.get(&id) {
-        Some(task) => Ok(serde_json::json!({
-            "id": id,
-            "task": task,
-            "status": "completed",
-        }).to_string()),
-        None => Ok(serde_json::json!({
-            "id": id,
-            "status": "not_found",
-        }).to_string()),
+/// CLI arguments for a chat turn, up to but not including the message
+fn chat_flags<'a>(model: Option<&'a str>, attachments: &'a [AttachmentRef]) -> Vec<&'a str> {
+    let mut args = vec!["--json", "chat"];
+    if let Some(m) = model {
+        args.push("--model");
+        args.push(m);
+    }
+    for attachment in attachments {
+        args.push("--attach");
+        args.push(&attachment.cli_path);
     }
+    args
 }
 
-#[tauri::command]
-async fn get_all_swarms(state: State<'_, NexusState>) -> Result<Vec<String>, String> {
-    let swarms = state.active_swarms.lock().await;
-    Ok(swarms.keys().cloned().collect())
+/// Run one non-interactive chat turn, optionally pinned to a specific model
+async fn run_chat(
+    message: &str,
+    model: Option<&str>,
+    attachments: &[AttachmentRef],
+    state: &NexusState,
+) -> Result<ChatReply, String> {
+    let mut args = chat_flags(model, attachments);
+    args.push(message);
+
+    let start = std::time::Instant::now();
+    let response = execute_nexus_bridge(&args, state).await?;
+    let latency_ms = start.elapsed().as_millis() as u64;
+    if let Some(error) = reported_error(&response) {
+        return Err(error);
+    }
+
+    let content = parse_chat_response(&response);
+    let usage = measure_usage(&response, message, &content, model, latency_ms, state).await;
+    Ok(ChatReply { content, usage })
 }
 
-#[tauri::command]
-async fn send_chat_message(message: String, state: State<'_, NexusState>) -> Result<String, String> {
-    // Store user message
-    let user_msg = ChatMessageRecord {
-        id: uuid::Uuid::new_v4().to_string(),
-        role: "user".to_string(),
-        content: message.clone(),
-        timestamp: chrono::Utc::now().to_rfc3339(),
-        is_streaming: false,
+/// Build usage metadata for a reply, filling model/provider from the CLI
+/// config when the response does not name them, and pricing the tokens.
+async fn measure_usage(
+    raw: &str,
+    prompt: &str,
+    reply: &str,
+    model_override: Option<&str>,
+    latency_ms: u64,
+    state: &NexusState,
+) -> MessageUsage {
+    let mut usage = usage::from_response(raw, prompt, reply, latency_ms);
+    if let Some(m) = model_override {
+        usage.model = Some(m.to_string());
+    }
+    if usage.model.is_none() || usage.provider.is_none() {
+        let (provider, model) = get_provider_and_model_from_config(state).await;
+        usage.provider = usage.provider.or(provider);
+        usage.model = usage.model.or(model);
+    }
+    usage.cost = usage::cost_for(usage.model.as_deref(), usage.prompt_tokens + usage.completion_tokens);
+    usage
+}
+
+fn persist_conversation(app: &tauri::AppHandle, conversation: &Conversation) {
+    if let Err(e) = conversation::save(app, conversation) {
+        eprintln!("[Tauri] Failed to save conversation {}: {}", conversation.id, e);
+    }
+}
+
+/// Append a message to the active branch and save the conversation.
+/// Messages carrying usage are also added to the usage ledger.
+async fn record_message(msg: ChatMessageRecord, app: &tauri::AppHandle, state: &NexusState) {
+    let ledger_entry = msg.usage.clone().map(|usage| UsageRecord {
+        timestamp: msg.timestamp.clone(),
+        source: "chat".to_string(),
+        conversation_id: None,
+        message_id: Some(msg.id.clone()),
+        project: None,
+        usage,
+    });
+
+    let conversation_id = {
+        let mut conv = state.chat_history.lock().await;
+        conv.push(msg);
+        persist_conversation(app, &conv);
+        conv.id.clone()
     };
-    state.chat_history.lock().await.push(user_msg);
 
-    // Send to nexus CLI
-    let response = execute_nexus_bridge(&["--json", "chat", &message], &state).await?;
+    if let Some(mut entry) = ledger_entry {
+        entry.conversation_id = Some(conversation_id);
+        record_usage(entry, app, state).await;
+    }
+}
 
-    // Parse response and extract the actual content
-    let content = if let Ok(json) = serde_json::from_str::<serde_json::Value>(&response) {
-        if json["success"].as_bool() == Some(true) {
-            json["data"]["response"].as_str().unwrap_or(&response).to_string()
-        } else {
-            json["error"].as_str().unwrap_or("Unknown error").to_string()
+/// Add an entry to the usage ledger, persist it and raise budget warnings
+/// for any thresholds it crosses
+async fn record_usage(mut entry: UsageRecord, app: &tauri::AppHandle, state: &NexusState) {
+    if entry.project.is_none() {
+        entry.project = state.current_project.lock().await
+            .as_ref().map(|p| p.to_string_lossy().to_string());
+    }
+
+    let warnings = {
+        let mut ledger = state.usage_ledger.lock().await;
+        ledger.push(entry);
+        if let Err(e) = usage::save(app, &ledger) {
+            eprintln!("[Tauri] Failed to save usage ledger: {}", e);
         }
-    } else {
-        response.clone()
+
+        let mut book = state.budgets.lock().await;
+        let warnings = budget::new_warnings(&mut book, &ledger);
+        if !warnings.is_empty() {
+            if let Err(e) = budget::save(app, &book) {
+                eprintln!("[Tauri] Failed to save budgets: {}", e);
+            }
+        }
+        warnings
     };
 
-    // Store assistant message
-    let assistant_msg = ChatMessageRecord {
-        id: uuid::Uuid::new_v4().to_string(),
-        role: "assistant".to_string(),
-        content: content.clone(),
-        timestamp: chrono::Utc::now().to_rfc3339(),
-        is_streaming: false,
+    for warning in warnings {
+        let _ = app.emit("nexus://budget-warning", serde_json::json!({
+            "message": warning,
+        }));
+        let _ = app.notification()
+            .builder()
+            .title("Nexus budget warning")
+            .body(&warning)
+            .show();
+    }
+}
+
+/// Hard stop for requests whose estimated cost would exceed a budget,
+/// unless the caller explicitly overrides it
+async fn enforce_budget(prompt: &str, model: Option<&str>, override_budget: bool, state: &NexusState) -> Result<(), String> {
+    if override_budget {
+        return Ok(());
+    }
+    let book = state.budgets.lock().await.clone();
+    if book.budgets.is_empty() {
+        return Ok(());
+    }
+
+    let (provider, configured_model) = get_provider_and_model_from_config(state).await;
+    let model = model.map(|m| m.to_string()).or(configured_model);
+    let estimate = budget::estimate(model.as_deref(), prompt);
+    let project = state.current_project.lock().await
+        .as_ref().map(|p| p.to_string_lossy().to_string());
+
+    let ledger = state.usage_ledger.lock().await;
+    let ctx = SpendContext {
+        provider: provider.as_deref(),
+        project: project.as_deref(),
     };
-    state.chat_history.lock().await.push(assistant_msg);
+    budget::check(&book, &ledger, &ctx, estimate.cost)
+}
 
-    Ok(content)
+/// Resolve attachment paths and stage local files on the SSH host. Files
+/// read where the project is, rather than uploaded from the desktop, must
+/// be within the project's allowed paths.
+async fn prepare_attachments(paths: &[String], state: &NexusState) -> Result<Vec<AttachmentRef>, String> {
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
+    let project = state.current_project.lock().await.clone();
+    let creds = state.ssh_credentials.lock().await.clone();
+    let refs = match creds {
+        None => attachments::prepare(paths, project.as_deref(), None)?,
+        Some(creds) => {
+            // Uploads can run to tens of megabytes, so they get their own
+            // session rather than holding up every other command on the
+            // shared one
+            let paths = paths.to_vec();
+            tokio::task::spawn_blocking(move || {
+                let sess = establish_ssh(&creds)?;
+                attachments::prepare(&paths, project.as_deref(), Some(&sess))
+            })
+            .await
+            .map_err(|e| e.to_string())??
+        }
+    };
+    for attachment in refs.iter().filter(|a| !a.uploaded) {
+        projects::check_allowed(Path::new(&attachment.cli_path), state).await?;
+    }
+    Ok(refs)
+}
+
+/// Built-in, per-user and per-project prompt templates, with project
+/// templates (`.nexus/prompts/*.md`) taking precedence over user ones
+async fn load_prompt_templates(app: &tauri::AppHandle, state: &NexusState) -> Vec<PromptTemplate> {
+    let user = app.path().app_config_dir()
+        .map(|dir| templates::load_local(&dir.join("prompts"), "user"))
+        .unwrap_or_default();
+
+    let project = match state.current_project.lock().await.clone() {
+        Some(root) => {
+            let dir = root.join(".nexus").join("prompts");
+            match with_ssh_session(state, |sess| Ok(templates::load_sftp(sess, &dir, "project"))).await {
+                Some(result) => result.unwrap_or_default(),
+                None => templates::load_local(&dir, "project"),
+            }
+        }
+        None => Vec::new(),
+    };
+
+    templates::merge(vec![templates::builtin(), user, project])
+}
+
+/// Expand a leading `/template` slash command before the message is sent
+async fn expand_slash_command(message: String, app: &tauri::AppHandle, state: &NexusState) -> Result<String, String> {
+    if !message.starts_with('/') {
+        return Ok(message);
+    }
+    let available = load_prompt_templates(app, state).await;
+    templates::expand_slash_command(&message, &available)
+}
+
+/// Store a user message as a child of the active leaf, returning its id
+async fn record_user_message(
+    content: String,
+    attachments: Vec<AttachmentRef>,
+    app: &tauri::AppHandle,
+    state: &NexusState,
+) -> String {
+    let mut conv = state.chat_history.lock().await;
+    let mut msg = ChatMessageRecord::new("user", content, conv.active_leaf.clone());
+    msg.attachments = attachments;
+    let id = msg.id.clone();
+    conv.push(msg);
+    persist_conversation(app, &conv);
+    id
+}
+
+/// Notify that a chat reply finished or failed, if it took long enough
+/// that the user may have switched away
+async fn notify_chat(started: std::time::Instant, result: Result<&str, &str>, app: &tauri::AppHandle, state: &NexusState) {
+    let min_secs = state.notification_settings.lock().await.chat_min_secs;
+    if started.elapsed().as_secs() < min_secs {
+        return;
+    }
+    let conversation_id = state.chat_history.lock().await.id.clone();
+    let (title, body) = match result {
+        Ok(reply) => ("Nexus replied", notifications::summary(reply)),
+        Err(error) => ("Nexus chat failed", notifications::summary(error)),
+    };
+    notifications::notify(app, NotifyEvent::Chat, result.is_ok(), title, &body, FocusTarget {
+        kind: "conversation".to_string(),
+        id: Some(conversation_id),
+    }).await;
+}
+
+#[tauri::command]
+async fn send_chat_message(
+    message: String,
+    attachments: Option<Vec<String>>,
+    override_budget: Option<bool>,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<String, String> {
+    let message = expand_slash_command(message, &app, &state).await?;
+    enforce_budget(&message, None, override_budget.unwrap_or(false), &state).await?;
+
+    let attachments = prepare_attachments(&attachments.unwrap_or_default(), &state).await?;
+    let user_id = record_user_message(message.clone(), attachments.clone(), &app, &state).await;
+
+    let start = std::time::Instant::now();
+    let reply = run_chat(&message, None, &attachments, &state).await;
+    notify_chat(start, reply.as_ref().map(|r| r.content.as_str()).map_err(|e| e.as_str()), &app, &state).await;
+    let reply = reply?;
+
+    let mut assistant_msg = ChatMessageRecord::new("assistant", reply.content.clone(), Some(user_id));
+    assistant_msg.usage = Some(reply.usage);
+    record_message(assistant_msg, &app, &state).await;
+
+    Ok(reply.content)
 }
 
 /// Streaming chat: reads SSH output incrementally and emits events per chunk
@@ -494,29 +848,31 @@ async fn send_chat_message(message: String, state: State<'_, NexusState>) -> Res
 async fn send_chat_message_stream(
     message: String,
     message_id: String,
+    attachments: Option<Vec<String>>,
+    override_budget: Option<bool>,
     app: tauri::AppHandle,
     state: State<'_, NexusState>,
 ) -> Result<(), String> {
-    // Store user message
-    let user_msg = ChatMessageRecord {
-        id: uuid::Uuid::new_v4().to_string(),
-        role: "user".to_string(),
-        content: message.clone(),
-        timestamp: chrono::Utc::now().to_rfc3339(),
-        is_streaming: false,
-    };
-    state.chat_history.lock().await.push(user_msg);
+    let message = expand_slash_command(message, &app, &state).await?;
+    enforce_budget(&message, None, override_budget.unwrap_or(false), &state).await?;
+
+    let attachments = prepare_attachments(&attachments.unwrap_or_default(), &state).await?;
+    let user_id = record_user_message(message.clone(), attachments.clone(), &app, &state).await;
 
     // Try SSH streaming
     let lock = state.ssh_session.lock().await;
     if let Some(sess) = lock.as_ref() {
         let mut channel = sess.channel_session().map_err(|e| e.to_string())?;
-        let cmd = format!("nexus --json chat \"{}\"", message.replace('"', "\\\""));
+        let mut args = chat_flags(None, &attachments);
+        args.push(&message);
+        let cmd = nexus_command(&args);
+        let start = std::time::Instant::now();
         channel.exec(&cmd).map_err(|e| e.to_string())?;
 
         // Read incrementally in small chunks
         let mut buf = [0u8; 1024];
         let mut full_output = String::new();
+        let mut failure = None;
         loop {
             match channel.read(&mut buf) {
                 Ok(0) => break,
@@ -529,35 +885,37 @@ async fn send_chat_message_stream(
                     }));
                 }
                 Err(e) => {
-                    let _ = app.emit("nexus://chat-error", serde_json::json!({
-                        "messageId": message_id,
-                        "error": e.to_string(),
-                    }));
+                    failure = Some(format!("Lost the reply stream: {}", e));
                     break;
                 }
             }
         }
+        let mut stderr = String::new();
+        let _ = channel.stderr().read_to_string(&mut stderr);
         channel.wait_close().ok();
+        let exit_code = channel.exit_status().unwrap_or(-1);
+        drop(lock);
+        let latency_ms = start.elapsed().as_millis() as u64;
+
+        let failure = failure
+            .or_else(|| reported_error(&full_output))
+            .or_else(|| (exit_code != 0).then(|| match stderr.trim() {
+                "" => format!("nexus exited with status {}", exit_code),
+                error => error.to_string(),
+            }));
+        if let Some(error) = failure {
+            // Returned rather than emitted, so the UI ends the stream once
+            notify_chat(start, Err(&error), &app, &state).await;
+            return Err(error);
+        }
 
-        // Parse final response for chat history
-        let content = if let Ok(json) = serde_json::from_str::<serde_json::Value>(&full_output) {
-            if json["success"].as_bool() == Some(true) {
-                json["data"]["response"].as_str().unwrap_or(&full_output).to_string()
-            } else {
-                full_output.clone()
-            }
-        } else {
-            full_output
-        };
-
-        let assistant_msg = ChatMessageRecord {
-            id: message_id.clone(),
-            role: "assistant".to_string(),
-            content,
-            timestamp: chrono::Utc::now().to_rfc3339(),
-            is_streaming: false,
-        };
-        state.chat_history.lock().await.push(assistant_msg);
+        let content = parse_chat_response(&full_output);
+        notify_chat(start, Ok(&content), &app, &state).await;
+        let usage = measure_usage(&full_output, &message, &content, None, latency_ms, &state).await;
+        let mut assistant_msg = ChatMessageRecord::new("assistant", content, Some(user_id));
+        assistant_msg.id = message_id.clone();
+        assistant_msg.usage = Some(usage);
+        record_message(assistant_msg, &app, &state).await;
 
         let _ = app.emit("nexus://chat-done", serde_json::json!({
             "messageId": message_id,
@@ -567,7 +925,25 @@ async fn send_chat_message_stream(
     drop(lock);
 
     // Fallback: non-streaming
-    let response = execute_nexus_bridge(&["--json", "chat", &message], &state).await?;
+    let mut args = chat_flags(None, &attachments);
+    args.push(&message);
+    let start = std::time::Instant::now();
+    let response = execute_nexus_bridge(&args, &state).await
+        .and_then(|raw| reported_error(&raw).map_or(Ok(raw), Err));
+    if let Err(e) = &response {
+        notify_chat(start, Err(e), &app, &state).await;
+    }
+    let response = response?;
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    let content = parse_chat_response(&response);
+    notify_chat(start, Ok(&content), &app, &state).await;
+    let usage = measure_usage(&response, &message, &content, None, latency_ms, &state).await;
+    let mut assistant_msg = ChatMessageRecord::new("assistant", content, Some(user_id));
+    assistant_msg.id = message_id.clone();
+    assistant_msg.usage = Some(usage);
+    record_message(assistant_msg, &app, &state).await;
+
     let _ = app.emit("nexus://chat-chunk", serde_json::json!({
         "messageId": message_id,
         "chunk": response,
@@ -578,18 +954,221 @@ async fn send_chat_message_stream(
     Ok(())
 }
 
+/// Messages on the active branch of the current conversation
 #[tauri::command]
 async fn get_chat_history(state: State<'_, NexusState>) -> Result<Vec<String>, String> {
-    let history = state.chat_history.lock().await;
-    Ok(history.iter().map(|m| serde_json::to_string(m).unwrap_or_default()).collect())
+    let conv = state.chat_history.lock().await;
+    Ok(conv.active_path().iter().map(|m| serde_json::to_string(m).unwrap_or_default()).collect())
 }
 
+/// Start a fresh conversation; the previous one stays on disk
 #[tauri::command]
 async fn clear_chat_history(state: State<'_, NexusState>) -> Result<(), String> {
-    state.chat_history.lock().await.clear();
+    *state.chat_history.lock().await = Conversation::new();
+    Ok(())
+}
+
+/// Ask again for an assistant message, optionally with a different model.
+/// The new answer becomes a sibling branch of the original.
+#[tauri::command]
+async fn regenerate_message(
+    id: String,
+    model: Option<String>,
+    override_budget: Option<bool>,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<String, String> {
+    let (parent_id, prompt, attachments) = {
+        let conv = state.chat_history.lock().await;
+        let msg = conv.get(&id).ok_or_else(|| format!("Message not found: {}", id))?;
+        if msg.role != "assistant" {
+            return Err("Only assistant messages can be regenerated".into());
+        }
+        let parent_id = msg.parent_id.clone()
+            .ok_or("Message has no prompt to regenerate from")?;
+        let parent = conv.get(&parent_id).ok_or("Prompt for this message is missing")?;
+        (parent_id, parent.content.clone(), parent.attachments.clone())
+    };
+
+    enforce_budget(&prompt, model.as_deref(), override_budget.unwrap_or(false), &state).await?;
+
+    let result = run_chat(&prompt, model.as_deref(), &attachments, &state).await?;
+
+    let mut reply = ChatMessageRecord::new("assistant", result.content, Some(parent_id));
+    reply.model = model;
+    reply.usage = Some(result.usage);
+    let json = serde_json::to_string(&reply).map_err(|e| e.to_string())?;
+    record_message(reply, &app, &state).await;
+    Ok(json)
+}
+
+/// Edit a user message by forking a new branch with the changed prompt and
+/// a fresh answer. The original message and its replies are kept.
+#[tauri::command]
+async fn edit_message(
+    id: String,
+    content: String,
+    override_budget: Option<bool>,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<String, String> {
+    let content = expand_slash_command(content, &app, &state).await?;
+    enforce_budget(&content, None, override_budget.unwrap_or(false), &state).await?;
+
+    let (parent_id, attachments) = {
+        let conv = state.chat_history.lock().await;
+        let msg = conv.get(&id).ok_or_else(|| format!("Message not found: {}", id))?;
+        if msg.role != "user" {
+            return Err("Only user messages can be edited".into());
+        }
+        (msg.parent_id.clone(), msg.attachments.clone())
+    };
+
+    let result = run_chat(&content, None, &attachments, &state).await?;
+
+    // The branch only appears once it has a reply, so a failed edit leaves
+    // the conversation as it was
+    let mut edited = ChatMessageRecord::new("user", content.clone(), parent_id);
+    edited.attachments = attachments;
+    let edited_id = edited.id.clone();
+    record_message(edited, &app, &state).await;
+
+    let mut reply = ChatMessageRecord::new("assistant", result.content, Some(edited_id));
+    reply.usage = Some(result.usage);
+    let json = serde_json::to_string(&reply).map_err(|e| e.to_string())?;
+    record_message(reply, &app, &state).await;
+    Ok(json)
+}
+
+#[tauri::command]
+async fn list_prompt_templates(app: tauri::AppHandle, state: State<'_, NexusState>) -> Result<Vec<PromptTemplate>, String> {
+    Ok(load_prompt_templates(&app, &state).await)
+}
+
+#[tauri::command]
+async fn render_prompt_template(
+    name: String,
+    vars: HashMap<String, String>,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<String, String> {
+    let available = load_prompt_templates(&app, &state).await;
+    let template = available.iter()
+        .find(|t| t.name == name)
+        .ok_or_else(|| format!("Prompt template not found: {}", name))?;
+    templates::render(template, &vars)
+}
+
+#[tauri::command]
+async fn get_message_branches(id: String, state: State<'_, NexusState>) -> Result<BranchInfo, String> {
+    state.chat_history.lock().await.branch_info(&id)
+}
+
+/// Show the branch through `id` and return the new active path
+#[tauri::command]
+async fn switch_branch(
+    id: String,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<Vec<String>, String> {
+    let mut conv = state.chat_history.lock().await;
+    conv.switch_to(&id)?;
+    persist_conversation(&app, &conv);
+    Ok(conv.active_path().iter().map(|m| serde_json::to_string(m).unwrap_or_default()).collect())
+}
+
+/// Token and cost totals from the usage ledger, grouped by
+/// "conversation", "day" or "provider"
+#[tauri::command]
+async fn get_usage_summary(group_by: String, state: State<'_, NexusState>) -> Result<Vec<UsageBucket>, String> {
+    let ledger = state.usage_ledger.lock().await;
+    usage::aggregate(&ledger, &group_by)
+}
+
+/// Configured budgets with current daily and monthly spend
+#[tauri::command]
+async fn get_budgets(state: State<'_, NexusState>) -> Result<Vec<BudgetStatus>, String> {
+    // Cloned rather than held, since record_usage locks the ledger first
+    let book = state.budgets.lock().await.clone();
+    let ledger = state.usage_ledger.lock().await;
+    Ok(budget::status(&book, &ledger))
+}
+
+/// Create or replace the budget for a provider or project
+#[tauri::command]
+async fn set_budget(
+    scope: String,
+    target: String,
+    daily_limit: Option<f64>,
+    monthly_limit: Option<f64>,
+    warn_thresholds: Option<Vec<f64>>,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<(), String> {
+    if scope != "provider" && scope != "project" {
+        return Err(format!("Unknown budget scope: {}", scope));
+    }
+    let mut book = state.budgets.lock().await;
+    book.budgets.retain(|b| !(b.scope == scope && b.target == target));
+    book.budgets.push(Budget {
+        scope,
+        target,
+        daily_limit,
+        monthly_limit,
+        warn_thresholds: warn_thresholds.unwrap_or_else(budget::default_thresholds),
+    });
+    budget::save(&app, &book)
+}
+
+#[tauri::command]
+async fn remove_budget(
+    scope: String,
+    target: String,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<(), String> {
+    let mut book = state.budgets.lock().await;
+    book.budgets.retain(|b| !(b.scope == scope && b.target == target));
+    budget::save(&app, &book)
+}
+
+/// Estimated cost of sending a message, priced with the configured or given model
+#[tauri::command]
+async fn estimate_request_cost(
+    message: String,
+    model: Option<String>,
+    state: State<'_, NexusState>,
+) -> Result<CostEstimate, String> {
+    let model = match model {
+        Some(m) => Some(m),
+        None => get_provider_and_model_from_config(&state).await.1,
+    };
+    Ok(budget::estimate(model.as_deref(), &message))
+}
+
+#[tauri::command]
+async fn get_notification_settings(state: State<'_, NexusState>) -> Result<NotificationSettings, String> {
+    Ok(state.notification_settings.lock().await.clone())
+}
+
+#[tauri::command]
+async fn set_notification_settings(
+    settings: NotificationSettings,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<(), String> {
+    notifications::save(&app, &settings)?;
+    *state.notification_settings.lock().await = settings;
     Ok(())
 }
 
+/// Swarm or conversation from the last background notification, if the UI
+/// has not been told about it yet (e.g. focus came before its listener)
+#[tauri::command]
+async fn take_focus_target(state: State<'_, NexusState>) -> Result<Option<FocusTarget>, String> {
+    Ok(state.pending_focus.lock().await.take().map(|(_, target)| target))
+}
+
 #[tauri::command]
 async fn get_memory_stats(state: State<'_, NexusState>) -> Result<String, String> {
     execute_nexus_bridge(&["--json", "memory-stats"], &state).await
@@ -609,18 +1188,36 @@ async fn memory_consolidate(state: State<'_, NexusState>) -> Result<(), String>
 
 #[tauri::command]
 async fn get_watcher_status(state: State<'_, NexusState>) -> Result<String, String> {
-    execute_nexus_bridge(&["--json", "watcher-status"], &state).await
+    let cli = execute_nexus_bridge(&["--json", "watcher-status"], &state).await;
+    let fs_watcher = match state.file_watcher.lock().await.as_ref() {
+        Some(w) => serde_json::json!({ "running": w.is_alive(), "project": w.project, "mode": w.mode }),
+        None => serde_json::json!({ "running": false }),
+    };
+
+    // The CLI's own status, with the desktop watcher alongside it
+    let mut status = cli.ok()
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+        .filter(|v| v.is_object())
+        .unwrap_or_else(|| serde_json::json!({}));
+    status["fsWatcher"] = fs_watcher;
+    Ok(status.to_string())
 }
 
+/// Start watching `path`, or the current project, for file changes
 #[tauri::command]
-async fn watch_start(_state: State<'_, NexusState>) -> Result<(), String> {
-    // Watcher runs in interactive mode on the CLI side
-    // For desktop, we just report the status
-    Ok(())
+async fn watch_start(path: Option<String>, app: tauri::AppHandle, state: State<'_, NexusState>) -> Result<(), String> {
+    let project = match path {
+        Some(path) => path,
+        None => state.current_project.lock().await
+            .as_ref().map(|p| p.to_string_lossy().to_string())
+            .ok_or("No project selected")?,
+    };
+    watcher::start(&app, &state, project).await
 }
 
 #[tauri::command]
-async fn watch_stop(_state: State<'_, NexusState>) -> Result<(), String> {
+async fn watch_stop(state: State<'_, NexusState>) -> Result<(), String> {
+    watcher::stop(&state).await;
     Ok(())
 }
 
@@ -708,12 +1305,15 @@ async fn reconnect_ssh(state: State<'_, NexusState>) -> Result<(), String> {
 #[tauri::command]
 async fn set_provider(provider: String, state: State<'_, NexusState>) -> Result<(), String> {
     execute_nexus_bridge(&["--json", "config", "set", "provider", &provider], &state).await?;
+    // The model follows the provider, so read both again when next needed
+    *state.cli_model.lock().await = None;
     Ok(())
 }
 
 #[tauri::command]
 async fn set_model(model: String, state: State<'_, NexusState>) -> Result<(), String> {
     execute_nexus_bridge(&["--json", "config", "set", "model", &model], &state).await?;
+    *state.cli_model.lock().await = None;
     Ok(())
 }
 
@@ -923,19 +1523,32 @@ async fn daemon_status(
 
 #[tauri::command]
 async fn daemon_run_tasks(
+    app: tauri::AppHandle,
     state: State<'_, NexusState>
 ) -> Result<(), String> {
-    let raw = execute_nexus_bridge(&["--json", "daemon", "run-tasks"], &state).await?;
+    let result = async {
+        let raw = execute_nexus_bridge(&["--json", "daemon", "run-tasks"], &state).await?;
 
-    if let Ok(json) = serde_json::from_str::<serde_json::Value>(&raw) {
-        if json["success"].as_bool() == Some(true) {
-            return Ok(());
-        } else {
-            return Err(json["error"].as_str().unwrap_or("Failed to run daemon tasks").to_string());
+        if let Ok(json) = serde_json::from_str::<serde_json::Value>(&raw) {
+            if json["success"].as_bool() == Some(true) {
+                return Ok(());
+            } else {
+                return Err(json["error"].as_str().unwrap_or("Failed to run daemon tasks").to_string());
+            }
         }
-    }
 
-    Err("Failed to parse daemon run tasks response".to_string())
+        Err("Failed to parse daemon run tasks response".to_string())
+    }.await;
+
+    let (title, body) = match &result {
+        Ok(()) => ("Daemon run finished", "Nexus daemon tasks completed".to_string()),
+        Err(e) => ("Daemon run failed", notifications::summary(e)),
+    };
+    notifications::notify(&app, NotifyEvent::Daemon, result.is_ok(), title, &body, FocusTarget {
+        kind: "daemon".to_string(),
+        id: None,
+    }).await;
+    result
 }
 
 // ============================================================================
@@ -1004,11 +1617,16 @@ async fn hierarchy_set_model(
 
 #[tauri::command]
 async fn get_model_capabilities(
-    state: State<'_, NexusState>
+    _state: State<'_, NexusState>
 ) -> Result<Vec<serde_json::Value>, String> {
+    Ok(model_capabilities())
+}
+
+/// Known models with their scores and pricing, also used for cost accounting
+fn model_capabilities() -> Vec<serde_json::Value> {
     // For now, return a hardcoded list since we don't have a CLI command to fetch capabilities
     // In future, could add: nexus models list-capabilities --json
-    Ok(vec![
+    vec![
         serde_json::json!({
             "id": "claude-opus-4-6",
             "provider": "claude",
@@ -1081,25 +1699,132 @@ async fn get_model_capabilities(
             "coding_score": 6,
             "cost_per_1m_tokens": 0.0,
         }),
-    ])
+    ]
 }
 
 fn main() {
     tauri::Builder::default()
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_dialog::init())
         .manage(NexusState::new())
+        .setup(|app| {
+            // Restore the most recent conversation from disk
+            if let Some(conv) = conversation::load_latest(app.handle()) {
+                if let Ok(mut history) = app.state::<NexusState>().chat_history.try_lock() {
+                    *history = conv;
+                }
+            }
+            if let Ok(mut ledger) = app.state::<NexusState>().usage_ledger.try_lock() {
+                *ledger = usage::load(app.handle());
+            }
+            if let Ok(mut book) = app.state::<NexusState>().budgets.try_lock() {
+                *book = budget::load(app.handle());
+            }
+            if let Ok(mut settings) = app.state::<NexusState>().notification_settings.try_lock() {
+                *settings = notifications::load(app.handle());
+            }
+            if let Ok(mut config) = app.state::<NexusState>().swarm_config.try_lock() {
+                *config = swarm::load_config(app.handle());
+            }
+            if let Ok(mut registry) = app.state::<NexusState>().projects.try_lock() {
+                *registry = projects::load(app.handle());
+                // Local projects can be reopened straight away; SSH ones
+                // wait for connect_remote
+                let last = registry.most_recent(None)
+                    .map(|p| p.path.clone())
+                    .filter(|p| Path::new(p).is_dir());
+                if let Some(path) = last {
+                    let handle = app.handle().clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = projects::select(&handle, &path, &handle.state::<NexusState>()).await {
+                            eprintln!("[Tauri] Not reopening {}: {}", path, e);
+                        }
+                    });
+                }
+            }
+            if let Ok(mut swarms) = app.state::<NexusState>().active_swarms.try_lock() {
+                *swarms = swarm::history::load_all(app.handle());
+                // Swarms that never started are picked up by the scheduler
+                if let Ok(mut queue) = app.state::<NexusState>().swarm_queue.try_lock() {
+                    queue.extend(swarm::history::queued(&swarms));
+                }
+                app.state::<NexusState>().swarm_wakeup.notify_one();
+            }
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                swarm::history::prune(&handle, &handle.state::<NexusState>()).await;
+            });
+            tauri::async_runtime::spawn(swarm::scheduler(app.handle().clone()));
+            tauri::async_runtime::spawn(git::watch_branch(app.handle().clone()));
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Focused(true) = event {
+                notifications::on_focus(window.app_handle());
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             connect_remote,
             get_nexus_status,
             scan_project,
             set_current_project,
             get_current_project,
-            start_swarm_task,
-            get_swarm_status,
-            get_all_swarms,
+            projects::list_projects,
+            projects::pin_project,
+            projects::set_project_overrides,
+            projects::remove_project,
+            files::list_directory,
+            files::read_file,
+            files::write_file,
+            transfer::upload_path,
+            transfer::download_path,
+            search::search_project,
+            search::cancel_search,
+            git::git_status,
+            git::git_diff,
+            git::git_log,
+            git::git_branches,
+            git::git_stage,
+            git::git_unstage,
+            git::git_commit,
+            git::git_checkout_branch,
+            git::git_create_branch,
+            git::generate_commit_message,
+            swarm::start_swarm_task,
+            swarm::get_swarm_status,
+            swarm::get_all_swarms,
+            swarm::cancel_swarm,
+            swarm::pause_swarm,
+            swarm::resume_swarm,
+            swarm::get_swarm_config,
+            swarm::set_swarm_config,
+            swarm::history::list_swarm_history,
+            swarm::history::search_swarm_history,
+            swarm::history::delete_swarm_record,
+            swarm::history::get_swarm_retention,
+            swarm::history::set_swarm_retention,
+            swarm::history::rerun_swarm,
+            swarm::review::get_swarm_diff,
+            swarm::review::review_swarm_change,
+            swarm::review::rollback_swarm,
             send_chat_message,
             send_chat_message_stream,
             get_chat_history,
             clear_chat_history,
+            regenerate_message,
+            edit_message,
+            get_message_branches,
+            switch_branch,
+            list_prompt_templates,
+            render_prompt_template,
+            get_usage_summary,
+            get_budgets,
+            set_budget,
+            remove_budget,
+            estimate_request_cost,
+            get_notification_settings,
+            set_notification_settings,
+            take_focus_target,
             get_memory_stats,
             memory_init,
             memory_consolidate,
@@ -1135,3 +1860,21 @@ fn main() {
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nexus_command_passes_each_argument_verbatim() {
+        let prompt = "it's a \"diff\"\n+ echo $(whoami) `id`\n- rm -rf $HOME; exit 1";
+        let cmd = nexus_command(&["--json", "chat", prompt]);
+
+        // Stand in for the CLI with printf, which echoes its arguments back
+        let script = cmd.replacen("nexus", "printf '%s\\0'", 1);
+        let output = std::process::Command::new("sh").arg("-c").arg(&script).output().unwrap();
+        let echoed = String::from_utf8(output.stdout).unwrap();
+        let args: Vec<&str> = echoed.trim_end_matches('\0').split('\0').collect();
+        assert_eq!(args, ["--json", "chat", prompt]);
+    }
+}
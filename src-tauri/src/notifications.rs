@@ -0,0 +1,156 @@
+// Native notifications for work that finishes while Nexus is in the background
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::NexusState;
+
+/// A notification is no longer offered to the UI once it is this old
+const FOCUS_TARGET_TTL: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyEvent {
+    Swarm,
+    Chat,
+    Daemon,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventSettings {
+    pub on_success: bool,
+    pub on_failure: bool,
+}
+
+impl Default for EventSettings {
+    fn default() -> Self {
+        Self { on_success: true, on_failure: true }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationSettings {
+    pub swarm: EventSettings,
+    pub chat: EventSettings,
+    pub daemon: EventSettings,
+    pub only_when_unfocused: bool,
+    pub chat_min_secs: u64,             // Chats quicker than this never notify
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            swarm: EventSettings::default(),
+            chat: EventSettings::default(),
+            daemon: EventSettings::default(),
+            only_when_unfocused: true,
+            chat_min_secs: 20,
+        }
+    }
+}
+
+impl NotificationSettings {
+    fn wants(&self, event: NotifyEvent, success: bool) -> bool {
+        let settings = match event {
+            NotifyEvent::Swarm => &self.swarm,
+            NotifyEvent::Chat => &self.chat,
+            NotifyEvent::Daemon => &self.daemon,
+        };
+        if success { settings.on_success } else { settings.on_failure }
+    }
+}
+
+/// What a notification was about, for the UI to bring up when the user
+/// comes back to the app
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusTarget {
+    pub kind: String,                   // "swarm", "conversation" or "daemon"
+    pub id: Option<String>,
+}
+
+fn window_focused(app: &tauri::AppHandle) -> bool {
+    app.webview_windows()
+        .values()
+        .any(|w| w.is_focused().unwrap_or(false))
+}
+
+/// Show a native notification for a finished event if the settings allow
+/// it. While the window is in the background `target` is remembered, and
+/// the next time the window is focused it is sent to the UI. Desktop
+/// notifications report no clicks, so that focus is the closest signal
+/// that the user came back because of it.
+pub async fn notify(
+    app: &tauri::AppHandle,
+    event: NotifyEvent,
+    success: bool,
+    title: &str,
+    body: &str,
+    target: FocusTarget,
+) {
+    let state = app.state::<NexusState>();
+    let settings = state.notification_settings.lock().await.clone();
+    if !settings.wants(event, success) || (settings.only_when_unfocused && window_focused(app)) {
+        return;
+    }
+
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        eprintln!("[Tauri] Failed to show notification: {}", e);
+        return;
+    }
+    // With the window already focused there is no coming back to it
+    if !window_focused(app) {
+        *state.pending_focus.lock().await = Some((Instant::now(), target));
+    }
+}
+
+/// Window regained focus: if a notification was shown while it was in the
+/// background, tell the UI once which swarm or conversation it was about
+pub fn on_focus(app: &tauri::AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let pending = app.state::<NexusState>().pending_focus.lock().await.take();
+        if let Some((at, target)) = pending {
+            if at.elapsed() < FOCUS_TARGET_TTL {
+                let _ = app.emit("nexus://focus-target", target);
+            }
+        }
+    });
+}
+
+/// First line of `text`, cut to fit a notification body
+pub fn summary(text: &str) -> String {
+    let line = text.lines().find(|l| !l.trim().is_empty()).unwrap_or("").trim();
+    if line.chars().count() > 120 {
+        format!("{}…", line.chars().take(119).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+// ============================================================================
+// Persistence
+// ============================================================================
+
+fn settings_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir.join("notification_settings.json"))
+}
+
+pub fn load(app: &tauri::AppHandle) -> NotificationSettings {
+    settings_path(app)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(app: &tauri::AppHandle, settings: &NotificationSettings) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    std::fs::write(settings_path(app)?, json).map_err(|e| e.to_string())
+}
@@ -0,0 +1,128 @@
+// Streaming process runner - runs a command on the active host and reports
+// its output line by line
+
+use std::io::{BufRead, BufReader};
+use std::process::Stdio;
+use tokio::io::AsyncBufReadExt;
+use tokio::process::Command as TokioCommand;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::{establish_ssh, shell_quote, SshCredentials};
+
+#[derive(Debug)]
+pub enum ProcessEvent {
+    Started(u32),                       // PID on the target host
+    Line(String),                       // One line of combined stdout/stderr
+    Exited(i32),
+    Failed(String),
+}
+
+/// Start `command` in `dir` and stream its output. Runs over a dedicated SSH
+/// session when credentials are given (so long runs don't hold the shared
+/// session) and through the local `sh` otherwise. The process `exec`s in
+/// place of the shell, so the reported PID can be signalled directly.
+pub fn spawn_lines(creds: Option<SshCredentials>, dir: Option<&str>, command: &str) -> UnboundedReceiver<ProcessEvent> {
+    let script = match dir {
+        Some(d) => format!("echo $$; cd {} && exec {} 2>&1", shell_quote(d), command),
+        None => format!("echo $$; exec {} 2>&1", command),
+    };
+    let (tx, rx) = unbounded_channel();
+    match creds {
+        Some(c) => {
+            std::thread::spawn(move || run_remote(c, script, tx));
+        }
+        None => {
+            tauri::async_runtime::spawn(run_local(script, tx));
+        }
+    }
+    rx
+}
+
+/// Forward one raw output line; the first line is the PID echoed by the wrapper
+fn forward_line(raw: &[u8], pid_seen: &mut bool, tx: &UnboundedSender<ProcessEvent>) -> bool {
+    let line = String::from_utf8_lossy(raw).trim_end_matches(['\n', '\r']).to_string();
+    let event = if *pid_seen {
+        ProcessEvent::Line(line)
+    } else {
+        *pid_seen = true;
+        match line.trim().parse() {
+            Ok(pid) => ProcessEvent::Started(pid),
+            Err(_) => ProcessEvent::Line(line),
+        }
+    };
+    tx.send(event).is_ok()
+}
+
+fn run_remote(creds: SshCredentials, script: String, tx: UnboundedSender<ProcessEvent>) {
+    let result = (|| -> Result<i32, String> {
+        let sess = establish_ssh(&creds)?;
+        let mut channel = sess.channel_session().map_err(|e| e.to_string())?;
+        channel.exec(&script).map_err(|e| e.to_string())?;
+
+        let mut reader = BufReader::new(&mut channel);
+        let mut buf = Vec::new();
+        let mut pid_seen = false;
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) => break,
+                Ok(_) => {
+                    if !forward_line(&buf, &mut pid_seen, &tx) {
+                        break;
+                    }
+                }
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        channel.wait_close().ok();
+        Ok(channel.exit_status().unwrap_or(-1))
+    })();
+
+    let _ = tx.send(match result {
+        Ok(code) => ProcessEvent::Exited(code),
+        Err(e) => ProcessEvent::Failed(e),
+    });
+}
+
+async fn run_local(script: String, tx: UnboundedSender<ProcessEvent>) {
+    let child = TokioCommand::new("sh")
+        .arg("-c")
+        .arg(&script)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn();
+    let mut child = match child {
+        Ok(c) => c,
+        Err(e) => {
+            let _ = tx.send(ProcessEvent::Failed(format!("Local execution failed: {}", e)));
+            return;
+        }
+    };
+
+    if let Some(stdout) = child.stdout.take() {
+        let mut reader = tokio::io::BufReader::new(stdout);
+        let mut buf = Vec::new();
+        let mut pid_seen = false;
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf).await {
+                Ok(0) => break,
+                Ok(_) => {
+                    if !forward_line(&buf, &mut pid_seen, &tx) {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    let _ = tx.send(ProcessEvent::Failed(e.to_string()));
+                    return;
+                }
+            }
+        }
+    }
+
+    let _ = tx.send(match child.wait().await {
+        Ok(status) => ProcessEvent::Exited(status.code().unwrap_or(-1)),
+        Err(e) => ProcessEvent::Failed(e.to_string()),
+    });
+}
@@ -0,0 +1,586 @@
+// Project registry - recently opened projects per host, with pinning and
+// per-project overrides applied whenever a project is selected, and checks
+// of what a project directory contains
+
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use tauri::{Manager, State};
+
+use crate::{
+    execute_nexus_bridge, execute_shell_checked, refresh_provider_and_model, shell_quote, NexusState,
+    SshCredentials,
+};
+
+/// Unpinned projects beyond this many are forgotten, oldest first
+const MAX_RECENT: usize = 50;
+
+/// Files whose presence at the project root identifies a language or tool:
+/// (file name, language, tooling)
+const MARKERS: &[(&str, Option<&str>, Option<&str>)] = &[
+    ("Cargo.toml", Some("Rust"), Some("cargo")),
+    ("package.json", Some("JavaScript"), Some("npm")),
+    ("tsconfig.json", Some("TypeScript"), None),
+    ("pnpm-lock.yaml", None, Some("pnpm")),
+    ("yarn.lock", None, Some("yarn")),
+    ("bun.lockb", None, Some("bun")),
+    ("deno.json", Some("TypeScript"), Some("deno")),
+    ("pyproject.toml", Some("Python"), None),
+    ("requirements.txt", Some("Python"), Some("pip")),
+    ("setup.py", Some("Python"), Some("setuptools")),
+    ("poetry.lock", None, Some("poetry")),
+    ("uv.lock", None, Some("uv")),
+    ("go.mod", Some("Go"), Some("go")),
+    ("pom.xml", Some("Java"), Some("maven")),
+    ("build.gradle", Some("Java"), Some("gradle")),
+    ("build.gradle.kts", Some("Kotlin"), Some("gradle")),
+    ("Gemfile", Some("Ruby"), Some("bundler")),
+    ("composer.json", Some("PHP"), Some("composer")),
+    ("mix.exs", Some("Elixir"), Some("mix")),
+    ("pubspec.yaml", Some("Dart"), Some("pub")),
+    ("Package.swift", Some("Swift"), Some("swiftpm")),
+    ("CMakeLists.txt", Some("C/C++"), Some("cmake")),
+    ("Makefile", None, Some("make")),
+    ("Dockerfile", None, Some("docker")),
+    ("docker-compose.yml", None, Some("docker compose")),
+    ("compose.yaml", None, Some("docker compose")),
+    ("flake.nix", None, Some("nix")),
+];
+
+/// What `set_current_project` found at a project's path
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProjectMetadata {
+    pub path: String,                   // Canonical, symlinks resolved
+    pub repo_root: Option<String>,      // Top level of the enclosing git repository
+    pub languages: Vec<String>,
+    pub tooling: Vec<String>,
+    pub markers: Vec<String>,           // Marker files found at the root
+}
+
+/// Settings that replace the CLI's defaults while a project is current
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProjectOverrides {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub hierarchy_preset: Option<String>,
+    /// Directories outside the project that file commands may touch. When
+    /// set, file commands are confined to the project and these.
+    pub allowed_paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectEntry {
+    pub path: String,
+    pub host: Option<String>,           // "user@host:port" for SSH projects, None for local ones
+    pub name: String,
+    pub last_opened: String,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub overrides: ProjectOverrides,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ProjectMetadata>,  // As of the last time it was opened
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectRegistry {
+    pub projects: Vec<ProjectEntry>,
+    /// Kept on disk so overrides can still be undone after a restart
+    #[serde(skip_serializing_if = "SavedSettings::is_empty")]
+    saved_settings: SavedSettings,
+}
+
+impl ProjectRegistry {
+    pub fn find(&self, host: Option<&str>, path: &str) -> Option<&ProjectEntry> {
+        self.projects.iter().find(|p| p.host.as_deref() == host && p.path == path)
+    }
+
+    fn find_mut(&mut self, host: Option<&str>, path: &str) -> Option<&mut ProjectEntry> {
+        self.projects.iter_mut().find(|p| p.host.as_deref() == host && p.path == path)
+    }
+
+    /// The project on `host` that was opened last
+    pub fn most_recent(&self, host: Option<&str>) -> Option<&ProjectEntry> {
+        self.projects.iter()
+            .filter(|p| p.host.as_deref() == host)
+            .max_by(|a, b| a.last_opened.cmp(&b.last_opened))
+    }
+
+    /// Record that a project was opened, adding it if it is new
+    fn touch(&mut self, host: Option<&str>, path: &str, metadata: ProjectMetadata) -> ProjectEntry {
+        let now = chrono::Utc::now().to_rfc3339();
+        if let Some(entry) = self.find_mut(host, path) {
+            entry.last_opened = now;
+            entry.metadata = Some(metadata);
+            return entry.clone();
+        }
+        let entry = ProjectEntry {
+            path: path.to_string(),
+            host: host.map(|h| h.to_string()),
+            name: Path::new(path).file_name()
+                .map_or_else(|| path.to_string(), |n| n.to_string_lossy().to_string()),
+            last_opened: now,
+            pinned: false,
+            overrides: ProjectOverrides::default(),
+            metadata: Some(metadata),
+        };
+        self.projects.push(entry.clone());
+        self.prune();
+        entry
+    }
+
+    fn prune(&mut self) {
+        self.projects.sort_by(|a, b| b.last_opened.cmp(&a.last_opened));
+        let mut unpinned = 0;
+        self.projects.retain(|p| {
+            if p.pinned {
+                return true;
+            }
+            unpinned += 1;
+            unpinned <= MAX_RECENT
+        });
+    }
+}
+
+/// How the active connection is named in the registry
+pub fn host_key(creds: Option<&SshCredentials>) -> Option<String> {
+    creds.map(|c| format!("{}@{}:{}", c.username, c.host, c.port))
+}
+
+async fn current_host(state: &NexusState) -> Option<String> {
+    host_key(state.ssh_credentials.lock().await.as_ref())
+}
+
+/// CLI settings as they were before the current project's overrides
+/// replaced them, put back when another project is selected
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct SavedSettings {
+    provider: Option<String>,
+    model: Option<String>,
+    hierarchy: Option<serde_json::Value>,   // `hierarchy show` data
+}
+
+impl SavedSettings {
+    fn is_empty(&self) -> bool {
+        self.provider.is_none() && self.model.is_none() && self.hierarchy.is_none()
+    }
+
+    /// CLI commands that put these settings back
+    fn restore_commands(&self) -> Vec<Vec<String>> {
+        let mut commands = Vec::new();
+        if let Some(provider) = &self.provider {
+            commands.push(vec!["config".into(), "set".into(), "provider".into(), provider.clone()]);
+        }
+        if let Some(model) = &self.model {
+            commands.push(vec!["config".into(), "set".into(), "model".into(), model.clone()]);
+        }
+        if let Some(categories) = self.hierarchy.as_ref().and_then(|h| h.as_object()) {
+            for (category, tiers) in categories {
+                for (tier, entry) in tiers.as_array().into_iter().flatten().enumerate() {
+                    if let Some(model_id) = entry["model_id"].as_str() {
+                        commands.push(vec![
+                            "hierarchy".into(), "set-model".into(), category.clone(), tier.to_string(), model_id.to_string(),
+                        ]);
+                    }
+                }
+            }
+        }
+        commands
+    }
+}
+
+/// Run `nexus --json <args>`, reading a `success: false` reply as an error
+async fn run_cli(args: &[String], state: &NexusState) -> Result<serde_json::Value, String> {
+    let mut full = vec!["--json"];
+    full.extend(args.iter().map(|a| a.as_str()));
+    let raw = execute_nexus_bridge(&full, state).await?;
+    let json: serde_json::Value = serde_json::from_str(&raw).map_err(|_| raw.trim().to_string())?;
+    if json["success"].as_bool() == Some(false) {
+        return Err(json["error"].as_str().unwrap_or("Unknown error").to_string());
+    }
+    Ok(json)
+}
+
+/// Push a project's provider, model and hierarchy overrides to the CLI,
+/// first putting back whatever the previous project's overrides replaced.
+/// Failures are logged rather than blocking the project switch.
+async fn apply_overrides(app: &tauri::AppHandle, overrides: &ProjectOverrides, state: &NexusState) {
+    let saved = state.projects.lock().await.saved_settings.clone();
+
+    // Note the settings these overrides replace, as they will be once the
+    // previous project's are undone
+    let mut replaced = SavedSettings::default();
+    if overrides.provider.is_some() || overrides.model.is_some() {
+        let (provider, model) = if saved.provider.is_some() || saved.model.is_some() {
+            (saved.provider.clone(), saved.model.clone())
+        } else {
+            refresh_provider_and_model(state).await
+        };
+        replaced.provider = provider;
+        replaced.model = model;
+    }
+    if overrides.hierarchy_preset.is_some() {
+        replaced.hierarchy = match &saved.hierarchy {
+            Some(hierarchy) => Some(hierarchy.clone()),
+            None => run_cli(&["hierarchy".into(), "show".into()], state).await
+                .map(|json| json["data"].clone())
+                .map_err(|e| eprintln!("[Tauri] Failed to read the model hierarchy: {}", e))
+                .ok(),
+        };
+    }
+
+    let mut commands = saved.restore_commands();
+    if let Some(provider) = &overrides.provider {
+        commands.push(vec!["config".into(), "set".into(), "provider".into(), provider.clone()]);
+    }
+    if let Some(model) = &overrides.model {
+        commands.push(vec!["config".into(), "set".into(), "model".into(), model.clone()]);
+    }
+    if let Some(preset) = &overrides.hierarchy_preset {
+        commands.push(vec!["hierarchy".into(), "set-preset".into(), preset.clone()]);
+    }
+    for args in commands {
+        if let Err(e) = run_cli(&args, state).await {
+            eprintln!("[Tauri] Failed to apply project settings `{}`: {}", args.join(" "), e);
+        }
+    }
+    *state.cli_model.lock().await = None;
+
+    let mut registry = state.projects.lock().await;
+    registry.saved_settings = replaced;
+    if let Err(e) = save(app, &registry) {
+        eprintln!("[Tauri] Failed to save project registry: {}", e);
+    }
+}
+
+/// Check that `path` is a readable directory on the active host and look
+/// at what it holds: its canonical path, enclosing git repository and the
+/// marker files that give away its languages and tooling
+pub async fn inspect(path: &str, state: &NexusState) -> Result<ProjectMetadata, String> {
+    // A leading ~ is expanded on the host, since quoting would keep it literal
+    let quoted = match path.strip_prefix("~/") {
+        Some(rest) => format!("\"$HOME\"/{}", shell_quote(rest)),
+        None if path == "~" => "\"$HOME\"".to_string(),
+        None => shell_quote(path),
+    };
+    let script = format!(
+        "p={path}; \
+         if [ ! -e \"$p\" ]; then echo missing; \
+         elif [ ! -d \"$p\" ]; then echo not-dir; \
+         elif [ ! -r \"$p\" ] || [ ! -x \"$p\" ]; then echo denied; \
+         else cd \"$p\" && printf 'ok\\npath=%s\\nrepo=%s\\n' \"$(pwd -P)\" \"$(git rev-parse --show-toplevel 2>/dev/null)\" && ls -A; fi",
+        path = quoted,
+    );
+    parse_inspect(path, &execute_shell_checked(&script, state).await?)
+}
+
+/// Read the output of the `inspect` script: a status line, then for a
+/// usable directory `path=` and `repo=` lines and one entry per line.
+/// Anything else is an error rather than an empty project.
+fn parse_inspect(path: &str, output: &str) -> Result<ProjectMetadata, String> {
+    let mut lines = output.lines();
+    let unexpected = |line: Option<&str>| match line {
+        Some(line) => format!("Unexpected output while inspecting {}: {:?}", path, line),
+        None => format!("Output ended early while inspecting {}", path),
+    };
+    match lines.next() {
+        Some("ok") => {}
+        Some("missing") => return Err(format!("No such file or directory: {}", path)),
+        Some("not-dir") => return Err(format!("Not a directory: {}", path)),
+        Some("denied") => return Err(format!("Permission denied: {}", path)),
+        other => return Err(unexpected(other)),
+    }
+    let mut field = |name: &str| {
+        let line = lines.next();
+        line.and_then(|l| l.strip_prefix(name))
+            .and_then(|l| l.strip_prefix('='))
+            .ok_or_else(|| unexpected(line))
+    };
+    let canonical = field("path")?;
+    if !canonical.starts_with('/') {
+        return Err(format!("Could not resolve {}", path));
+    }
+    let repo_root = Some(field("repo")?).filter(|r| !r.is_empty()).map(|r| r.to_string());
+    let entries: Vec<&str> = lines.collect();
+
+    let mut metadata = ProjectMetadata {
+        path: canonical.to_string(),
+        repo_root,
+        ..ProjectMetadata::default()
+    };
+    for (file, language, tool) in MARKERS {
+        if !entries.contains(file) {
+            continue;
+        }
+        metadata.markers.push(file.to_string());
+        for (value, list) in [(language, &mut metadata.languages), (tool, &mut metadata.tooling)] {
+            if let Some(value) = value {
+                if !list.iter().any(|v| v == value) {
+                    list.push(value.to_string());
+                }
+            }
+        }
+    }
+    // .NET projects are named after the project rather than a fixed file
+    if entries.iter().any(|e| e.ends_with(".csproj") || e.ends_with(".sln")) {
+        metadata.languages.push("C#".to_string());
+        metadata.tooling.push("dotnet".to_string());
+    }
+    Ok(metadata)
+}
+
+/// Make `path` the current project on the active host after checking it:
+/// record it in the registry under its canonical path and apply its
+/// overrides
+pub async fn select(app: &tauri::AppHandle, path: &str, state: &NexusState) -> Result<ProjectEntry, String> {
+    let metadata = inspect(path, state).await?;
+    let path = metadata.path.clone();
+    *state.current_project.lock().await = Some(PathBuf::from(&path));
+
+    let host = current_host(state).await;
+    let entry = {
+        let mut registry = state.projects.lock().await;
+        let entry = registry.touch(host.as_deref(), &path, metadata);
+        if let Err(e) = save(app, &registry) {
+            eprintln!("[Tauri] Failed to save project registry: {}", e);
+        }
+        entry
+    };
+    apply_overrides(app, &entry.overrides, state).await;
+    Ok(entry)
+}
+
+/// Check that `path` is inside the current project or one of its allowed
+/// paths, if the project restricts them. Symlinks are followed first, so a
+/// link inside the project can't lead anywhere else.
+pub async fn check_allowed(path: &Path, state: &NexusState) -> Result<(), String> {
+    let Some(project) = state.current_project.lock().await.clone() else {
+        return Ok(());
+    };
+    let host = current_host(state).await;
+    let allowed = state.projects.lock().await
+        .find(host.as_deref(), &project.to_string_lossy())
+        .map(|p| p.overrides.allowed_paths.clone())
+        .unwrap_or_default();
+    if allowed.is_empty() {
+        return Ok(());
+    }
+    let mut paths = vec![path.to_path_buf(), project];
+    paths.extend(allowed.iter().map(PathBuf::from));
+    let resolved = real_paths(&paths, state).await?;
+    let inside = resolved[0].as_ref().is_some_and(|path| {
+        resolved[1..].iter().flatten().any(|root| path.starts_with(root))
+    });
+    if inside {
+        return Ok(());
+    }
+    Err(format!("{} is outside the project and its allowed paths", path.display()))
+}
+
+/// Where each of `paths` really leads on the active host, with `.`, `..`
+/// and symlinks resolved even below the last component that exists. None
+/// for relative paths.
+async fn real_paths(paths: &[PathBuf], state: &NexusState) -> Result<Vec<Option<PathBuf>>, String> {
+    if state.ssh_credentials.lock().await.is_none() {
+        return Ok(paths.iter().map(|p| resolve_local(p)).collect());
+    }
+    let args: Vec<String> = paths.iter()
+        .filter(|p| p.is_absolute())
+        .map(|p| shell_quote(&p.to_string_lossy()))
+        .collect();
+    let output = execute_shell_checked(&format!("realpath -m -z -- {}", args.join(" ")), state).await?;
+    let mut resolved = output.split('\0').map(PathBuf::from);
+    Ok(paths.iter().map(|p| if p.is_absolute() { resolved.next() } else { None }).collect())
+}
+
+/// `realpath -m` for this machine: follow every symlink along `path`, with
+/// the components after the last existing one taken as they are. None for
+/// relative paths and symlink loops.
+fn resolve_local(path: &Path) -> Option<PathBuf> {
+    const MAX_LINKS: usize = 40;
+    if !path.is_absolute() {
+        return None;
+    }
+    let parts = |path: &Path| -> Vec<PathBuf> {
+        path.components().rev().map(|c| PathBuf::from(c.as_os_str())).collect()
+    };
+    // Components still to visit, last one first
+    let mut todo = parts(path);
+    let mut real = PathBuf::new();
+    let mut links = 0;
+    while let Some(part) = todo.pop() {
+        match part.components().next() {
+            Some(Component::ParentDir) => {
+                real.pop();
+            }
+            Some(Component::Normal(_)) => {
+                real.push(&part);
+                if let Ok(target) = std::fs::read_link(&real) {
+                    links += 1;
+                    if links > MAX_LINKS {
+                        return None;
+                    }
+                    // An absolute target restarts from the root
+                    real.pop();
+                    todo.extend(parts(&target));
+                }
+            }
+            Some(Component::CurDir) | None => {}
+            Some(Component::RootDir) | Some(Component::Prefix(_)) => real.push(&part),
+        }
+    }
+    Some(real)
+}
+
+/// Projects for `host` (the active one by default), pinned first and then
+/// most recently opened
+#[tauri::command]
+pub async fn list_projects(host: Option<String>, state: State<'_, NexusState>) -> Result<Vec<ProjectEntry>, String> {
+    let host = match host {
+        Some(host) => Some(host),
+        None => current_host(&state).await,
+    };
+    let mut projects: Vec<ProjectEntry> = state.projects.lock().await.projects.iter()
+        .filter(|p| p.host == host)
+        .cloned()
+        .collect();
+    projects.sort_by(|a, b| b.pinned.cmp(&a.pinned).then_with(|| b.last_opened.cmp(&a.last_opened)));
+    Ok(projects)
+}
+
+/// Apply `f` to a registered project on the active host and save
+async fn update_project(
+    app: &tauri::AppHandle,
+    path: &str,
+    state: &NexusState,
+    f: impl FnOnce(&mut ProjectEntry),
+) -> Result<ProjectEntry, String> {
+    let host = current_host(state).await;
+    let mut registry = state.projects.lock().await;
+    let entry = registry.find_mut(host.as_deref(), path)
+        .ok_or_else(|| format!("Project not found: {}", path))?;
+    f(entry);
+    let entry = entry.clone();
+    save(app, &registry)?;
+    Ok(entry)
+}
+
+#[tauri::command]
+pub async fn pin_project(
+    path: String,
+    pinned: bool,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<ProjectEntry, String> {
+    update_project(&app, &path, &state, |p| p.pinned = pinned).await
+}
+
+/// Replace a project's overrides, applying them right away if it is the
+/// current project
+#[tauri::command]
+pub async fn set_project_overrides(
+    path: String,
+    overrides: ProjectOverrides,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<ProjectEntry, String> {
+    let entry = update_project(&app, &path, &state, |p| p.overrides = overrides).await?;
+    let is_current = state.current_project.lock().await.as_deref() == Some(Path::new(&path));
+    if is_current {
+        apply_overrides(&app, &entry.overrides, &state).await;
+    }
+    Ok(entry)
+}
+
+/// Forget a project on the active host
+#[tauri::command]
+pub async fn remove_project(path: String, app: tauri::AppHandle, state: State<'_, NexusState>) -> Result<(), String> {
+    let host = current_host(&state).await;
+    let mut registry = state.projects.lock().await;
+    registry.projects.retain(|p| !(p.host == host && p.path == path));
+    save(&app, &registry)
+}
+
+// ============================================================================
+// Persistence
+// ============================================================================
+
+fn registry_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir.join("projects.json"))
+}
+
+pub fn load(app: &tauri::AppHandle) -> ProjectRegistry {
+    registry_path(app)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(app: &tauri::AppHandle, registry: &ProjectRegistry) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(registry).map_err(|e| e.to_string())?;
+    std::fs::write(registry_path(app)?, json).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_local_follows_links_out_of_the_project() {
+        use std::os::unix::fs::symlink;
+        let dir = std::env::temp_dir().join(format!("nexus-resolve-{}", uuid::Uuid::new_v4()));
+        let (project, outside) = (dir.join("project"), dir.join("outside"));
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::create_dir_all(outside.join("sub")).unwrap();
+        symlink(&outside, project.join("x")).unwrap();
+        symlink("loop", project.join("loop")).unwrap();
+        let outside = resolve_local(&outside).unwrap();
+
+        // Through a link, into a file that doesn't exist yet
+        assert_eq!(resolve_local(&project.join("x/new.txt")), Some(outside.join("new.txt")));
+        // `..` applies to where the link leads, as in `realpath`
+        assert_eq!(resolve_local(&project.join("x/sub/../a")), Some(outside.join("a")));
+        assert_eq!(resolve_local(&project.join("./y/../z")), Some(resolve_local(&project).unwrap().join("z")));
+        assert_eq!(resolve_local(&project.join("loop/a")), None);
+        assert_eq!(resolve_local(Path::new("relative/path")), None);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn inspection_reports_why_a_path_is_not_usable() {
+        let error = |output| parse_inspect("~/p", output).unwrap_err();
+        assert_eq!(error("missing\n"), "No such file or directory: ~/p");
+        assert_eq!(error("not-dir\n"), "Not a directory: ~/p");
+        assert_eq!(error("denied\n"), "Permission denied: ~/p");
+    }
+
+    #[test]
+    fn inspection_finds_repo_and_markers() {
+        let output = "ok\npath=/home/me/p\nrepo=/home/me\n.git\nCargo.toml\npackage.json\ntsconfig.json\ndeno.json\nApp.csproj\n";
+        let metadata = parse_inspect("~/p", output).unwrap();
+        assert_eq!(metadata.path, "/home/me/p");
+        assert_eq!(metadata.repo_root.as_deref(), Some("/home/me"));
+        assert_eq!(metadata.markers, ["Cargo.toml", "package.json", "tsconfig.json", "deno.json"]);
+        assert_eq!(metadata.languages, ["Rust", "JavaScript", "TypeScript", "C#"]);
+        assert_eq!(metadata.tooling, ["cargo", "npm", "deno", "dotnet"]);
+
+        let metadata = parse_inspect("/tmp/p", "ok\npath=/tmp/p\nrepo=\n").unwrap();
+        assert_eq!(metadata.repo_root, None);
+        assert!(metadata.markers.is_empty() && metadata.languages.is_empty());
+    }
+
+    #[test]
+    fn malformed_inspection_output_is_an_error() {
+        for output in ["", "ok\n", "ok\npath=/p\n", "ok\nrepo=\npath=/p\n", "ok\npath=p\nrepo=\n", "bash: cd: denied\n"] {
+            assert!(parse_inspect("/p", output).is_err(), "{:?}", output);
+        }
+    }
+}
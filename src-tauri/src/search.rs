@@ -0,0 +1,452 @@
+// Project search - ripgrep on the target host when it is installed, and a
+// built-in walker over std::fs or SFTP when it isn't. Matches stream to the
+// UI in batches as they are found.
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, State};
+
+use crate::files::{read_local, read_sftp, Entry, BINARY_SNIFF_BYTES};
+use crate::gitignore::{glob_match, Gitignore};
+use crate::projects::check_allowed;
+use crate::process::{self, ProcessEvent};
+use crate::{establish_ssh, execute_shell_bridge, shell_quote, NexusState};
+
+/// Matches are sent once this many are waiting, or after `FLUSH_INTERVAL`
+const BATCH_SIZE: usize = 100;
+const FLUSH_INTERVAL: Duration = Duration::from_millis(150);
+
+/// Longest preview sent per matching line
+const MAX_PREVIEW_CHARS: usize = 500;
+
+/// The built-in walker skips files bigger than this
+const MAX_FILE_BYTES: u64 = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchOptions {
+    pub path: Option<String>,           // Defaults to the current project
+    pub regex: bool,                    // Otherwise the query is a literal
+    pub case_sensitive: Option<bool>,   // None: smart case
+    pub whole_word: bool,
+    pub include: Vec<String>,           // Globs; without a `/` they match file names
+    pub exclude: Vec<String>,
+    pub max_results: usize,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            path: None,
+            regex: false,
+            case_sensitive: None,
+            whole_word: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            max_results: 2000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatch {
+    pub path: String,
+    pub line: u64,                      // 1-based
+    pub column: u64,                    // 1-based, in characters
+    pub match_length: u64,              // In characters
+    pub preview: String,                // The matching line
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchSummary {
+    pub id: String,
+    pub engine: &'static str,           // "ripgrep" or "builtin"
+    pub matches: usize,
+    pub files: usize,
+    pub truncated: bool,                // Stopped at max_results
+    pub cancelled: bool,
+}
+
+/// Collects matches and emits them as `nexus://search-results` batches
+struct Batch {
+    app: tauri::AppHandle,
+    id: String,
+    pending: Vec<SearchMatch>,
+    last_flush: Instant,
+    total: usize,
+    files: HashSet<String>,
+    max: usize,
+}
+
+impl Batch {
+    /// Queue a match; false once `max` matches have been collected
+    fn push(&mut self, found: SearchMatch) -> bool {
+        if self.total >= self.max {
+            return false;
+        }
+        self.total += 1;
+        self.files.insert(found.path.clone());
+        self.pending.push(found);
+        if self.pending.len() >= BATCH_SIZE || self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush();
+        }
+        self.total < self.max
+    }
+
+    fn flush(&mut self) {
+        self.last_flush = Instant::now();
+        if self.pending.is_empty() {
+            return;
+        }
+        let matches = std::mem::take(&mut self.pending);
+        let _ = self.app.emit("nexus://search-results", serde_json::json!({
+            "id": self.id,
+            "matches": matches,
+        }));
+    }
+
+    fn summary(&mut self, engine: &'static str, cancelled: bool) -> SearchSummary {
+        self.flush();
+        SearchSummary {
+            id: self.id.clone(),
+            engine,
+            matches: self.total,
+            files: self.files.len(),
+            truncated: self.total >= self.max,
+            cancelled,
+        }
+    }
+}
+
+fn preview(line: &str) -> String {
+    let line = line.trim_end_matches(['\n', '\r']);
+    if line.chars().count() > MAX_PREVIEW_CHARS {
+        line.chars().take(MAX_PREVIEW_CHARS).collect()
+    } else {
+        line.to_string()
+    }
+}
+
+/// Character column of byte offset `start` in `line`, 1-based
+fn column(line: &str, start: usize) -> u64 {
+    line.get(..start).map_or(0, |s| s.chars().count()) as u64 + 1
+}
+
+fn smart_case(query: &str, options: &SearchOptions) -> bool {
+    options.case_sensitive.unwrap_or_else(|| query.chars().any(char::is_uppercase))
+}
+
+// ============================================================================
+// ripgrep
+// ============================================================================
+
+fn rg_command(query: &str, root: &str, options: &SearchOptions) -> String {
+    let mut args = vec!["rg", "--json", "--max-columns", "500", "--max-columns-preview"];
+    args.push(if smart_case(query, options) { "-s" } else { "-i" });
+    if !options.regex {
+        args.push("-F");
+    }
+    if options.whole_word {
+        args.push("-w");
+    }
+    let mut command = args.join(" ");
+    for glob in &options.include {
+        command.push_str(&format!(" --glob {}", shell_quote(glob)));
+    }
+    for glob in &options.exclude {
+        command.push_str(&format!(" --glob {}", shell_quote(&format!("!{}", glob))));
+    }
+    command.push_str(&format!(" -e {} -- {}", shell_quote(query), shell_quote(root)));
+    command
+}
+
+/// Turn one `rg --json` match message into a match
+fn parse_rg(line: &str) -> Option<SearchMatch> {
+    let json: serde_json::Value = serde_json::from_str(line).ok()?;
+    if json["type"] != "match" {
+        return None;
+    }
+    let data = &json["data"];
+    let text = data["lines"]["text"].as_str()?;
+    let submatch = &data["submatches"][0];
+    let start = submatch["start"].as_u64().unwrap_or(0) as usize;
+    let end = submatch["end"].as_u64().unwrap_or(start as u64) as usize;
+    Some(SearchMatch {
+        path: data["path"]["text"].as_str()?.to_string(),
+        line: data["line_number"].as_u64()?,
+        column: column(text, start),
+        match_length: text.get(start..end).map_or(0, |m| m.chars().count()) as u64,
+        preview: preview(text),
+    })
+}
+
+async fn search_rg(
+    query: &str,
+    root: &str,
+    options: &SearchOptions,
+    batch: &mut Batch,
+    cancel: &AtomicBool,
+    state: &NexusState,
+) -> Result<bool, String> {
+    let creds = state.ssh_credentials.lock().await.clone();
+    let mut events = process::spawn_lines(creds, None, &rg_command(query, root, options));
+    let mut pid = None;
+    let mut last_error = String::new();
+    let mut stopped = false;
+
+    loop {
+        // Wake up now and then so a cancel lands even while rg is quiet
+        let event = match tokio::time::timeout(FLUSH_INTERVAL, events.recv()).await {
+            Ok(Some(event)) => event,
+            Ok(None) => break,
+            Err(_) if cancel.load(Ordering::Relaxed) => {
+                stopped = true;
+                break;
+            }
+            Err(_) => {
+                batch.flush();
+                continue;
+            }
+        };
+        match event {
+            ProcessEvent::Started(started) => pid = Some(started),
+            ProcessEvent::Line(line) => {
+                if let Some(found) = parse_rg(&line) {
+                    if !batch.push(found) || cancel.load(Ordering::Relaxed) {
+                        stopped = true;
+                        break;
+                    }
+                } else if !line.starts_with('{') {
+                    last_error = line;
+                }
+            }
+            // 1 is "no matches"; 2 with matches is an unreadable file or two
+            ProcessEvent::Exited(code) if code > 1 && batch.total == 0 => {
+                return Err(if last_error.is_empty() { format!("rg exited with status {}", code) } else { last_error });
+            }
+            ProcessEvent::Exited(_) => {}
+            ProcessEvent::Failed(e) => return Err(e),
+        }
+    }
+
+    if stopped {
+        if let Some(pid) = pid {
+            let _ = execute_shell_bridge(&format!("kill {}", pid), None, state).await;
+        }
+    }
+    Ok(cancel.load(Ordering::Relaxed))
+}
+
+// ============================================================================
+// Built-in walker
+// ============================================================================
+
+struct Walker<'a> {
+    root: &'a Path,
+    ignore: Gitignore,
+    regex: Regex,
+    options: &'a SearchOptions,
+    cancel: &'a AtomicBool,
+}
+
+impl Walker<'_> {
+    fn glob_hit(globs: &[String], rel: &str, name: &str) -> bool {
+        globs.iter().any(|g| {
+            let g = g.trim_start_matches('/');
+            let subject = if g.contains('/') { rel } else { name };
+            glob_match(g.as_bytes(), subject.as_bytes())
+        })
+    }
+
+    /// Search `dir` recursively; false once the search should stop
+    fn walk(
+        &self,
+        dir: &Path,
+        batch: &mut Batch,
+        list: &mut dyn FnMut(&Path) -> Result<Vec<Entry>, String>,
+        read: &mut dyn FnMut(&Path) -> Result<Vec<u8>, String>,
+    ) -> bool {
+        let Ok(mut entries) = list(dir) else { return true };
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        for entry in entries {
+            if self.cancel.load(Ordering::Relaxed) {
+                return false;
+            }
+            // Hidden files and directories are skipped, as rg does by default
+            if entry.name.starts_with('.') {
+                continue;
+            }
+            let path = dir.join(&entry.name);
+            let rel = path.strip_prefix(self.root).map(|p| p.to_string_lossy().replace('\\', "/")).unwrap_or_default();
+            if self.ignore.is_ignored(&rel, entry.is_dir) || Self::glob_hit(&self.options.exclude, &rel, &entry.name) {
+                continue;
+            }
+            if entry.is_dir {
+                if !entry.is_symlink && !self.walk(&path, batch, list, read) {
+                    return false;
+                }
+                continue;
+            }
+            let included = self.options.include.is_empty() || Self::glob_hit(&self.options.include, &rel, &entry.name);
+            if !included || entry.size.unwrap_or(0) > MAX_FILE_BYTES {
+                continue;
+            }
+            let Ok(bytes) = read(&path) else { continue };
+            if !self.search_file(&path, &bytes, batch) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn search_file(&self, path: &Path, bytes: &[u8], batch: &mut Batch) -> bool {
+        if bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+            return true;
+        }
+        let text = String::from_utf8_lossy(bytes);
+        for (number, line) in text.lines().enumerate() {
+            let Some(found) = self.regex.find(line) else { continue };
+            let hit = SearchMatch {
+                path: path.to_string_lossy().to_string(),
+                line: number as u64 + 1,
+                column: column(line, found.start()),
+                match_length: found.as_str().chars().count() as u64,
+                preview: preview(line),
+            };
+            if !batch.push(hit) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn build_regex(query: &str, options: &SearchOptions) -> Result<Regex, String> {
+    let pattern = if options.regex { query.to_string() } else { regex::escape(query) };
+    let pattern = if options.whole_word { format!(r"\b(?:{})\b", pattern) } else { pattern };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!smart_case(query, options))
+        .build()
+        .map_err(|e| format!("Invalid search pattern: {}", e))
+}
+
+/// Walk over SFTP on a dedicated session when connected, std::fs otherwise
+fn search_builtin(
+    creds: Option<crate::SshCredentials>,
+    root: &Path,
+    regex: Regex,
+    options: &SearchOptions,
+    batch: &mut Batch,
+    cancel: &AtomicBool,
+) -> Result<bool, String> {
+    match creds {
+        Some(creds) => {
+            let sess = establish_ssh(&creds)?;
+            let sftp = sess.sftp().map_err(|e| format!("SFTP unavailable: {}", e))?;
+            let walker = Walker { root, ignore: Gitignore::load_sftp(&sess, root), regex, options, cancel };
+            walker.walk(root, batch, &mut |dir| read_sftp(&sftp, dir), &mut |file| {
+                let mut bytes = Vec::new();
+                let mut f = sftp.open(file).map_err(|e| e.to_string())?;
+                f.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+                Ok(bytes)
+            });
+        }
+        None => {
+            let walker = Walker { root, ignore: Gitignore::load_local(root), regex, options, cancel };
+            walker.walk(root, batch, &mut read_local, &mut |file| std::fs::read(file).map_err(|e| e.to_string()));
+        }
+    }
+    Ok(cancel.load(Ordering::Relaxed))
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Search file contents under the current project (or `options.path`) on
+/// the active host. Matches arrive as `nexus://search-results` batches; the
+/// returned summary is also sent as `nexus://search-complete`. Both carry
+/// `id`, which the caller picks so it can tell batches of a cancelled search
+/// from the new one's. Starting a new search cancels the one still running.
+#[tauri::command]
+pub async fn search_project(
+    query: String,
+    id: Option<String>,
+    options: Option<SearchOptions>,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<SearchSummary, String> {
+    if query.is_empty() {
+        return Err("Search query is empty".into());
+    }
+    let options = options.unwrap_or_default();
+    let root = match options.path.clone() {
+        Some(path) => PathBuf::from(path),
+        None => state.current_project.lock().await.clone().ok_or("No project selected")?,
+    };
+    check_allowed(&root, &state).await?;
+    // Check the pattern up front so rg and the walker reject the same input
+    let regex = build_regex(&query, &options)?;
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    if let Some(previous) = state.active_search.lock().await.replace(cancel.clone()) {
+        previous.store(true, Ordering::Relaxed);
+    }
+
+    let id = id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let mut batch = Batch {
+        app: app.clone(),
+        id: id.clone(),
+        pending: Vec::new(),
+        last_flush: Instant::now(),
+        total: 0,
+        files: HashSet::new(),
+        max: options.max_results.max(1),
+    };
+
+    let has_rg = execute_shell_bridge("command -v rg", None, &state).await
+        .is_ok_and(|out| out.trim_start().starts_with('/'));
+    let result = if has_rg {
+        let root = root.to_string_lossy().to_string();
+        search_rg(&query, &root, &options, &mut batch, &cancel, &state).await
+            .map(|cancelled| batch.summary("ripgrep", cancelled))
+    } else {
+        let creds = state.ssh_credentials.lock().await.clone();
+        let flag = cancel.clone();
+        tokio::task::spawn_blocking(move || {
+            search_builtin(creds, &root, regex, &options, &mut batch, &flag)
+                .map(|cancelled| batch.summary("builtin", cancelled))
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    };
+
+    {
+        let mut active = state.active_search.lock().await;
+        if active.as_ref().is_some_and(|a| Arc::ptr_eq(a, &cancel)) {
+            *active = None;
+        }
+    }
+    let _ = match &result {
+        Ok(summary) => app.emit("nexus://search-complete", serde_json::json!({ "id": id, "summary": summary })),
+        Err(e) => app.emit("nexus://search-complete", serde_json::json!({ "id": id, "error": e })),
+    };
+    result
+}
+
+/// Stop the running search, if any
+#[tauri::command]
+pub async fn cancel_search(state: State<'_, NexusState>) -> Result<(), String> {
+    if let Some(active) = state.active_search.lock().await.take() {
+        active.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
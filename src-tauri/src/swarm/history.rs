@@ -0,0 +1,321 @@
+// Swarm history - persisted swarm records with retention, filtering and search
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::{Manager, State};
+
+use super::{queue_swarm, review, SwarmRecord, SwarmStatus};
+use crate::{enforce_budget, NexusState};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwarmRetention {
+    pub max_age_days: Option<u32>,
+    pub max_entries: Option<usize>,
+}
+
+impl Default for SwarmRetention {
+    fn default() -> Self {
+        Self {
+            max_age_days: Some(30),
+            max_entries: Some(200),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwarmHistoryFilter {
+    pub status: Option<SwarmStatus>,
+    pub project: Option<String>,
+    pub since: Option<String>,          // RFC 3339, inclusive
+    pub until: Option<String>,          // RFC 3339, exclusive
+    pub query: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwarmSearchHit {
+    pub id: String,
+    pub description: String,
+    pub status: SwarmStatus,
+    pub created_at: String,
+    pub snippet: String,                // Text around the first match
+}
+
+/// Text fields of a record that searches look at, in priority order
+fn searchable_text(record: &SwarmRecord) -> impl Iterator<Item = &str> {
+    std::iter::once(record.description.as_str())
+        .chain(std::iter::once(record.output.as_str()))
+        .chain(record.error.as_deref())
+        .chain(record.subtasks.iter().map(|s| s.description.as_str()))
+        .chain(record.subtasks.iter().filter_map(|s| s.output.as_deref()))
+}
+
+/// Lowercase one character at a time, keeping only the first character of
+/// each mapping, so the nth character of the result comes from the nth
+/// character of `text` ('İ' would otherwise become two characters)
+fn fold_case(text: &str) -> String {
+    text.chars()
+        .map(|c| c.to_lowercase().next().unwrap_or(c))
+        .collect()
+}
+
+/// Up to 80 characters either side of the first case-insensitive match.
+/// `needle` must already be folded with `fold_case`.
+fn snippet(text: &str, needle: &str) -> Option<String> {
+    let folded = fold_case(text);
+    let at = folded.find(needle)?;
+    let chars: Vec<char> = text.chars().collect();
+    let char_at = folded[..at].chars().count();
+    let start = char_at.saturating_sub(80);
+    let end = (char_at + needle.chars().count() + 80).min(chars.len());
+    Some(chars[start..end].iter().collect::<String>().replace('\n', " "))
+}
+
+fn matches(record: &SwarmRecord, filter: &SwarmHistoryFilter) -> bool {
+    if filter.status.is_some_and(|s| s != record.status) {
+        return false;
+    }
+    if filter.project.is_some() && filter.project != record.project {
+        return false;
+    }
+    if filter.since.as_ref().is_some_and(|since| record.created_at < *since) {
+        return false;
+    }
+    if filter.until.as_ref().is_some_and(|until| record.created_at >= *until) {
+        return false;
+    }
+    if let Some(query) = filter.query.as_deref().map(fold_case) {
+        return searchable_text(record).any(|text| fold_case(text).contains(&query));
+    }
+    true
+}
+
+/// Finished records that fall outside the retention policy
+pub fn expired(records: &HashMap<String, SwarmRecord>, retention: &SwarmRetention) -> Vec<String> {
+    let mut finished: Vec<&SwarmRecord> = records.values()
+        .filter(|r| r.status.is_finished())
+        .collect();
+    finished.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+    let cutoff = retention.max_age_days
+        .map(|days| (chrono::Utc::now() - chrono::Duration::days(days as i64)).to_rfc3339());
+    finished.iter()
+        .enumerate()
+        .filter(|(index, record)| {
+            retention.max_entries.is_some_and(|max| *index >= max)
+                || cutoff.as_ref().is_some_and(|c| record.created_at < *c)
+        })
+        .map(|(_, record)| record.id.clone())
+        .collect()
+}
+
+// ============================================================================
+// Persistence
+// ============================================================================
+
+fn history_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?.join("swarms");
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+pub fn save(app: &tauri::AppHandle, record: &SwarmRecord) -> Result<(), String> {
+    let path = history_dir(app)?.join(format!("{}.json", record.id));
+    let json = serde_json::to_string_pretty(record).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| e.to_string())
+}
+
+fn delete(app: &tauri::AppHandle, id: &str) -> Result<(), String> {
+    let path = history_dir(app)?.join(format!("{}.json", id));
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Load every saved swarm. Runs that had started when the app exited can't
+/// be resumed and are marked failed; queued ones are left for `queued`.
+pub fn load_all(app: &tauri::AppHandle) -> HashMap<String, SwarmRecord> {
+    let Ok(dir) = history_dir(app) else {
+        return HashMap::new();
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return HashMap::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|e| std::fs::read_to_string(e.path()).ok())
+        .filter_map(|raw| serde_json::from_str::<SwarmRecord>(&raw).ok())
+        .map(|mut record| {
+            if !record.status.is_finished() && record.status != SwarmStatus::Queued {
+                record.status = SwarmStatus::Failed;
+                record.pid = None;
+                record.error = Some("Interrupted by application restart".into());
+                record.completed_at.get_or_insert_with(|| chrono::Utc::now().to_rfc3339());
+                record.result = Some(record.build_result());
+                let _ = save(app, &record);
+            }
+            (record.id.clone(), record)
+        })
+        .collect()
+}
+
+/// Ids of swarms still waiting to start, oldest first
+pub fn queued(records: &HashMap<String, SwarmRecord>) -> Vec<String> {
+    let mut queued: Vec<&SwarmRecord> = records.values()
+        .filter(|r| r.status == SwarmStatus::Queued)
+        .collect();
+    queued.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    queued.into_iter().map(|r| r.id.clone()).collect()
+}
+
+fn retention_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir.join("swarm_retention.json"))
+}
+
+pub fn load_retention(app: &tauri::AppHandle) -> SwarmRetention {
+    retention_path(app)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+/// Remove a record's file and the git snapshots taken for it
+async fn discard(app: &tauri::AppHandle, record: &SwarmRecord, state: &NexusState) -> Result<(), String> {
+    if let (Some(project), Some(_)) = (&record.project, &record.base_snapshot) {
+        if let Err(e) = review::drop_snapshots(project, &record.id, state).await {
+            eprintln!("[Tauri] Failed to drop snapshots of swarm {}: {}", record.id, e);
+        }
+    }
+    delete(app, &record.id)
+}
+
+/// Drop expired records from memory and disk
+pub async fn prune(app: &tauri::AppHandle, state: &NexusState) {
+    let retention = load_retention(app);
+    let removed: Vec<SwarmRecord> = {
+        let mut swarms = state.active_swarms.lock().await;
+        expired(&swarms, &retention).iter()
+            .filter_map(|id| swarms.remove(id))
+            .collect()
+    };
+    for record in removed {
+        if let Err(e) = discard(app, &record, state).await {
+            eprintln!("[Tauri] Failed to delete swarm record {}: {}", record.id, e);
+        }
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Swarm records matching `filter`, newest first
+#[tauri::command]
+pub async fn list_swarm_history(
+    filter: Option<SwarmHistoryFilter>,
+    state: State<'_, NexusState>,
+) -> Result<Vec<SwarmRecord>, String> {
+    let filter = filter.unwrap_or_default();
+    let swarms = state.active_swarms.lock().await;
+    let mut records: Vec<SwarmRecord> = swarms.values()
+        .filter(|r| matches(r, &filter))
+        .cloned()
+        .collect();
+    records.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    if let Some(limit) = filter.limit {
+        records.truncate(limit);
+    }
+    Ok(records)
+}
+
+/// Case-insensitive search over descriptions, outputs, errors and subtasks
+#[tauri::command]
+pub async fn search_swarm_history(
+    query: String,
+    limit: Option<usize>,
+    state: State<'_, NexusState>,
+) -> Result<Vec<SwarmSearchHit>, String> {
+    let needle = fold_case(query.trim());
+    if needle.is_empty() {
+        return Ok(Vec::new());
+    }
+    let swarms = state.active_swarms.lock().await;
+    let mut hits: Vec<SwarmSearchHit> = swarms.values()
+        .filter_map(|record| {
+            let snippet = searchable_text(record).find_map(|text| snippet(text, &needle))?;
+            Some(SwarmSearchHit {
+                id: record.id.clone(),
+                description: record.description.clone(),
+                status: record.status,
+                created_at: record.created_at.clone(),
+                snippet,
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    hits.truncate(limit.unwrap_or(50));
+    Ok(hits)
+}
+
+#[tauri::command]
+pub async fn delete_swarm_record(id: String, app: tauri::AppHandle, state: State<'_, NexusState>) -> Result<(), String> {
+    let record = {
+        let mut swarms = state.active_swarms.lock().await;
+        match swarms.get(&id) {
+            Some(record) if !record.status.is_finished() => {
+                return Err("Cancel the swarm before deleting it".into());
+            }
+            Some(_) => swarms.remove(&id),
+            None => return Err(format!("Swarm not found: {}", id)),
+        }
+    };
+    match record {
+        Some(record) => discard(&app, &record, &state).await,
+        None => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub async fn get_swarm_retention(app: tauri::AppHandle) -> Result<SwarmRetention, String> {
+    Ok(load_retention(&app))
+}
+
+#[tauri::command]
+pub async fn set_swarm_retention(
+    retention: SwarmRetention,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(&retention).map_err(|e| e.to_string())?;
+    std::fs::write(retention_path(&app)?, json).map_err(|e| e.to_string())?;
+    prune(&app, &state).await;
+    Ok(())
+}
+
+/// Queue a new run of a past swarm with the same description and project
+#[tauri::command]
+pub async fn rerun_swarm(
+    id: String,
+    override_budget: Option<bool>,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<String, String> {
+    let previous = state.active_swarms.lock().await
+        .get(&id).cloned()
+        .ok_or_else(|| format!("Swarm not found: {}", id))?;
+    enforce_budget(&previous.description, None, override_budget.unwrap_or(false), &state).await?;
+
+    let task_id = queue_swarm(previous.description, previous.project, previous.cost_limit, &app, &state).await;
+    Ok(serde_json::json!({
+        "task_id": task_id,
+        "status": SwarmStatus::Queued,
+    }).to_string())
+}
@@ -0,0 +1,1341 @@
+// Swarm runs - background execution of multi-agent tasks with a real lifecycle
+
+pub mod history;
+mod planner;
+pub mod review;
+mod worktree;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{Emitter, Manager, State};
+
+use crate::notifications::{self, FocusTarget, NotifyEvent};
+use crate::process::{self, ProcessEvent};
+use crate::usage::{self, UsageRecord};
+use crate::{
+    chat_flags, enforce_budget, execute_shell_bridge, measure_usage, parse_chat_response, record_usage,
+    shell_quote, NexusState,
+};
+
+/// Base delay before the first retry; doubles with each further attempt
+const RETRY_BACKOFF_SECS: u64 = 5;
+
+/// Scheduler settings, matching `SwarmConfig` in the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwarmConfig {
+    pub max_concurrent_workers: usize,  // Swarms running at once
+    #[serde(default = "default_parallel_subtasks")]
+    pub max_parallel_subtasks: usize,   // Subtasks each running swarm runs at once
+    pub max_retries: u32,
+    pub task_timeout_secs: u64,         // Wall-clock limit per attempt, including time paused
+    pub auto_merge: bool,
+    #[serde(default)]
+    pub planner_model: Option<String>,  // Model for the planning step; the CLI default when unset
+    #[serde(default)]
+    pub default_cost_limit: Option<f64>,    // USD ceiling for swarms started without one
+}
+
+impl Default for SwarmConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_workers: 2,
+            max_parallel_subtasks: default_parallel_subtasks(),
+            max_retries: 1,
+            task_timeout_secs: 1800,
+            auto_merge: false,
+            planner_model: None,
+            default_cost_limit: None,
+        }
+    }
+}
+
+fn default_parallel_subtasks() -> usize {
+    2
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwarmStatus {
+    Queued,
+    Running,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl SwarmStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// Status of a single subtask, matching `TaskStatus` in the frontend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Pending,
+    InProgress,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl TaskStatus {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" | "queued" => Some(Self::Pending),
+            "in_progress" | "running" | "working" => Some(Self::InProgress),
+            "completed" | "done" => Some(Self::Completed),
+            "failed" | "error" => Some(Self::Failed),
+            "cancelled" => Some(Self::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Subtask {
+    pub id: String,
+    pub description: String,
+    pub agent_type: String,
+    pub status: TaskStatus,
+    pub dependencies: Vec<String>,
+    pub output: Option<String>,
+    pub files_modified: Vec<String>,
+    pub execution_time_ms: Option<u64>,
+    #[serde(default, skip_serializing)]
+    pub pid: Option<u32>,               // nexus process on the target host while running
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff_from: Option<String>,      // Project snapshots bracketing this subtask's changes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff_to: Option<String>,
+}
+
+impl Subtask {
+    fn pending(id: &str, description: &str, agent_type: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            description: description.to_string(),
+            agent_type: agent_type.to_string(),
+            status: TaskStatus::Pending,
+            dependencies: Vec::new(),
+            output: None,
+            files_modified: Vec::new(),
+            execution_time_ms: None,
+            pid: None,
+            diff_from: None,
+            diff_to: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkerInfo {
+    pub worker_type: String,
+    pub name: String,
+    pub status: String,                 // idle, working, completed, error or paused
+    pub current_task: Option<String>,
+    pub progress: u8,
+    pub last_result: Option<String>,
+    pub files_modified: Vec<String>,
+    #[serde(default)]
+    pub tokens_used: u64,
+    #[serde(default)]
+    pub cost: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwarmRecord {
+    pub id: String,
+    pub description: String,
+    pub status: SwarmStatus,
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub completed_at: Option<String>,
+    pub pid: Option<u32>,               // Planner process on the target host while running
+    pub attempts: u32,                  // 1 plus every retry of the planner or a subtask
+    pub progress: u8,
+    pub subtasks: Vec<Subtask>,
+    pub workers: Vec<WorkerInfo>,
+    pub output: String,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub project: Option<String>,        // Project directory the swarm runs in
+    #[serde(default)]
+    pub tokens_used: u64,               // Summed over all attempts
+    #[serde(default)]
+    pub cost: f64,
+    #[serde(default)]
+    pub cost_limit: Option<f64>,        // USD; the swarm is cancelled once `cost` exceeds it
+    #[serde(default)]
+    pub result: Option<SwarmResult>,    // Set once the swarm finishes
+    #[serde(default)]
+    pub base_snapshot: Option<String>,  // Project snapshot taken before the first subtask
+    #[serde(default)]
+    pub final_snapshot: Option<String>, // Project snapshot once every subtask was merged; review decisions refer to it
+    #[serde(default)]
+    pub review: HashMap<String, review::ReviewDecision>,
+    #[serde(default)]
+    pub rolled_back: bool,
+    #[serde(default)]
+    pub conflicts: Vec<MergeConflict>,  // Found merging subtask worktrees back
+}
+
+/// Outcome of a finished swarm, matching `SwarmResult` in the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwarmResult {
+    pub task_id: String,
+    pub success: bool,
+    pub subtask_results: Vec<SubtaskResult>,
+    pub merged_files: Vec<String>,
+    pub conflicts: Vec<MergeConflict>,
+    pub execution_time_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubtaskResult {
+    pub task_id: String,
+    pub worker_type: String,
+    pub success: bool,
+    pub output: String,
+    pub files_modified: Vec<String>,
+    pub execution_time_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeConflict {
+    pub file_path: String,
+    pub worker_a: String,
+    pub worker_b: String,
+    pub resolution: String,             // auto_merged, manual_required or skipped
+}
+
+/// A swarm as listed by `get_all_swarms`, with its place in the queue
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwarmSummary {
+    #[serde(flatten)]
+    pub record: SwarmRecord,
+    pub queue_position: Option<usize>,  // 0-based; None once started
+}
+
+impl SwarmRecord {
+    fn new(description: String, project: Option<String>, cost_limit: Option<f64>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            description,
+            status: SwarmStatus::Queued,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            started_at: None,
+            completed_at: None,
+            pid: None,
+            attempts: 1,
+            progress: 0,
+            subtasks: Vec::new(),
+            workers: Vec::new(),
+            output: String::new(),
+            error: None,
+            project,
+            tokens_used: 0,
+            cost: 0.0,
+            cost_limit,
+            result: None,
+            base_snapshot: None,
+            final_snapshot: None,
+            review: HashMap::new(),
+            rolled_back: false,
+            conflicts: Vec::new(),
+        }
+    }
+
+    /// Every nexus process currently running for this swarm
+    fn pids(&self) -> Vec<u32> {
+        self.pid.into_iter()
+            .chain(self.subtasks.iter().filter_map(|s| s.pid))
+            .collect()
+    }
+
+    /// Summarise the subtasks of a finished swarm
+    fn build_result(&self) -> SwarmResult {
+        let subtask_results: Vec<SubtaskResult> = self.subtasks.iter()
+            .map(|s| SubtaskResult {
+                task_id: s.id.clone(),
+                worker_type: s.agent_type.clone(),
+                success: s.status == TaskStatus::Completed,
+                output: s.output.clone().unwrap_or_default(),
+                files_modified: s.files_modified.clone(),
+                execution_time_ms: s.execution_time_ms.unwrap_or(0),
+            })
+            .collect();
+        let mut merged_files = Vec::new();
+        for subtask in &subtask_results {
+            merge_files(&mut merged_files, subtask.files_modified.clone());
+        }
+        let elapsed = |start: &str, end: &str| -> Option<u64> {
+            let start = chrono::DateTime::parse_from_rfc3339(start).ok()?;
+            let end = chrono::DateTime::parse_from_rfc3339(end).ok()?;
+            u64::try_from((end - start).num_milliseconds()).ok()
+        };
+        SwarmResult {
+            task_id: self.id.clone(),
+            success: self.status == SwarmStatus::Completed,
+            subtask_results,
+            merged_files,
+            conflicts: self.conflicts.clone(),
+            execution_time_ms: match (&self.started_at, &self.completed_at) {
+                (Some(start), Some(end)) => elapsed(start, end).unwrap_or(0),
+                _ => 0,
+            },
+        }
+    }
+}
+
+/// Write a swarm record to the history store, logging failures
+fn persist(app: &tauri::AppHandle, record: &SwarmRecord) {
+    if let Err(e) = history::save(app, record) {
+        eprintln!("[Tauri] Failed to save swarm {}: {}", record.id, e);
+    }
+}
+
+fn string_list(value: &serde_json::Value) -> Option<Vec<String>> {
+    value.as_array().map(|items| {
+        items.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect()
+    })
+}
+
+fn merge_files(existing: &mut Vec<String>, new: Vec<String>) {
+    for file in new {
+        if !existing.contains(&file) {
+            existing.push(file);
+        }
+    }
+}
+
+/// Payload for `nexus://swarm-progress`, optionally carrying the subtask or
+/// worker that changed
+fn progress_payload(record: &SwarmRecord, subtask: Option<&Subtask>, worker: Option<&WorkerInfo>) -> serde_json::Value {
+    let current = record.subtasks.iter()
+        .find(|s| s.status == TaskStatus::InProgress)
+        .map(|s| s.description.clone());
+    serde_json::json!({
+        "taskId": record.id,
+        "progress": record.progress,
+        "status": record.status,
+        "currentSubtask": current,
+        "subtask": subtask,
+        "worker": worker,
+    })
+}
+
+fn emit_progress(app: &tauri::AppHandle, record: &SwarmRecord) {
+    let _ = app.emit("nexus://swarm-progress", progress_payload(record, None, None));
+}
+
+/// Fold one streamed CLI line into the record. Events are JSON lines with a
+/// "type" of "subtask", "worker" or "progress"; anything else is ordinary
+/// output and yields `None`. Returns the progress payload to emit.
+fn apply_event(record: &mut SwarmRecord, line: &str) -> Option<serde_json::Value> {
+    let event: serde_json::Value = serde_json::from_str(line.trim()).ok()?;
+    match event["type"].as_str()? {
+        "subtask" => {
+            let id = event["id"].as_str()?;
+            let index = match record.subtasks.iter().position(|s| s.id == id) {
+                Some(i) => i,
+                None => {
+                    record.subtasks.push(Subtask::pending(id, "", "backend"));
+                    record.subtasks.len() - 1
+                }
+            };
+            let subtask = &mut record.subtasks[index];
+            if let Some(description) = event["description"].as_str() {
+                subtask.description = description.to_string();
+            }
+            if let Some(agent_type) = event["agent_type"].as_str() {
+                subtask.agent_type = agent_type.to_string();
+            }
+            if let Some(status) = event["status"].as_str().and_then(TaskStatus::parse) {
+                subtask.status = status;
+            }
+            if let Some(deps) = string_list(&event["dependencies"]) {
+                subtask.dependencies = deps;
+            }
+            if let Some(output) = event["output"].as_str() {
+                subtask.output = Some(output.to_string());
+            }
+            if let Some(files) = string_list(&event["files_modified"]) {
+                merge_files(&mut subtask.files_modified, files);
+            }
+            if let Some(ms) = event["execution_time_ms"].as_u64() {
+                subtask.execution_time_ms = Some(ms);
+            }
+
+            update_progress(record);
+            Some(progress_payload(record, Some(&record.subtasks[index]), None))
+        }
+        "worker" => {
+            let worker_type = event["worker_type"].as_str()?;
+            let index = match record.workers.iter().position(|w| w.worker_type == worker_type) {
+                Some(i) => i,
+                None => {
+                    record.workers.push(WorkerInfo {
+                        worker_type: worker_type.to_string(),
+                        name: worker_type.to_string(),
+                        status: "idle".to_string(),
+                        current_task: None,
+                        progress: 0,
+                        last_result: None,
+                        files_modified: Vec::new(),
+                        tokens_used: 0,
+                        cost: 0.0,
+                    });
+                    record.workers.len() - 1
+                }
+            };
+            let worker = &mut record.workers[index];
+            if let Some(name) = event["name"].as_str() {
+                worker.name = name.to_string();
+            }
+            if let Some(status) = event["status"].as_str() {
+                worker.status = status.to_string();
+            }
+            if !event["current_task"].is_null() {
+                worker.current_task = event["current_task"].as_str().map(|s| s.to_string());
+            }
+            if let Some(progress) = event["progress"].as_u64() {
+                worker.progress = progress.min(100) as u8;
+            }
+            if let Some(result) = event["last_result"].as_str() {
+                worker.last_result = Some(result.to_string());
+            }
+            if let Some(files) = string_list(&event["files_modified"]) {
+                merge_files(&mut worker.files_modified, files);
+            }
+            Some(progress_payload(record, None, Some(&record.workers[index])))
+        }
+        "progress" => {
+            record.progress = event["progress"].as_u64()?.min(100) as u8;
+            Some(progress_payload(record, None, None))
+        }
+        _ => None,
+    }
+}
+
+/// Usage reported by the CLI as it works
+struct UsageEvent {
+    worker_type: Option<String>,
+    model: Option<String>,
+    tokens: u64,
+    cost: Option<f64>,
+}
+
+/// Parse a `{"type": "usage", ...}` line streamed by the CLI
+fn usage_event(line: &str) -> Option<UsageEvent> {
+    let event: serde_json::Value = serde_json::from_str(line.trim()).ok()?;
+    if event["type"].as_str()? != "usage" {
+        return None;
+    }
+    let count = |a: &str, b: &str| event[a].as_u64().or_else(|| event[b].as_u64()).unwrap_or(0);
+    Some(UsageEvent {
+        worker_type: event["worker_type"].as_str().map(|s| s.to_string()),
+        model: event["model"].as_str().map(|s| s.to_string()),
+        tokens: count("prompt_tokens", "input_tokens") + count("completion_tokens", "output_tokens"),
+        cost: event["cost"].as_f64(),
+    })
+}
+
+/// Add spend to the swarm and to the worker it is charged to. Returns the
+/// ceiling when this pushed a still-running swarm over it.
+fn credit_usage(record: &mut SwarmRecord, worker: &str, tokens: u64, cost: f64) -> (WorkerInfo, Option<f64>) {
+    record.tokens_used += tokens;
+    record.cost += cost;
+    let worker = update_worker(record, worker, |w| {
+        w.tokens_used += tokens;
+        w.cost += cost;
+    });
+    let exceeded = record.cost_limit
+        .filter(|limit| record.cost > *limit && !record.status.is_finished());
+    (worker, exceeded)
+}
+
+/// Credit usage to a swarm, emitting the updated worker and cancelling the
+/// swarm if it went over its cost ceiling
+async fn charge(app: &tauri::AppHandle, state: &NexusState, id: &str, worker: &str, tokens: u64, cost: f64) {
+    let (exceeded, total) = {
+        let mut swarms = state.active_swarms.lock().await;
+        let Some(record) = swarms.get_mut(id) else { return };
+        let (worker, exceeded) = credit_usage(record, worker, tokens, cost);
+        let _ = app.emit("nexus://swarm-progress", progress_payload(record, None, Some(&worker)));
+        (exceeded, record.cost)
+    };
+    if let Some(limit) = exceeded {
+        let reason = format!("Cancelled: spent ${:.2}, over the ${:.2} cost ceiling", total, limit);
+        eprintln!("[Tauri] Swarm {} {}", id, reason);
+        if let Err(e) = cancel(app, state, id, Some(reason)).await {
+            eprintln!("[Tauri] Failed to cancel swarm {}: {}", id, e);
+        }
+    }
+}
+
+/// Send a signal to a swarm's nexus processes and the agents they spawned
+async fn signal_process(pids: &[u32], signal: &str, state: &NexusState) -> Result<(), String> {
+    if pids.is_empty() {
+        return Err("Swarm process has not started yet".into());
+    }
+    let list: Vec<String> = pids.iter().map(|p| p.to_string()).collect();
+    let cmd = format!(
+        "pkill -{sig} -P {parents} 2>/dev/null; kill -{sig} {pids}",
+        sig = signal,
+        parents = list.join(","),
+        pids = list.join(" "),
+    );
+    let output = execute_shell_bridge(&cmd, None, state).await?;
+    // Some subtasks may exit just before the signal; only fail when none were left
+    if output.matches("No such process").count() >= pids.len() {
+        return Err("Swarm process is no longer running".into());
+    }
+    Ok(())
+}
+
+/// Ask a swarm's processes to exit, paused or not. A stopped process only
+/// acts on SIGTERM once continued, so CONT follows; it changes nothing for
+/// one that is running.
+async fn terminate(pids: &[u32], state: &NexusState) -> Result<(), String> {
+    signal_process(pids, "TERM", state).await?;
+    // Fails harmlessly when TERM already ended them
+    let _ = signal_process(pids, "CONT", state).await;
+    Ok(())
+}
+
+/// Apply `f` to a swarm record, returning the updated copy
+async fn update_swarm(state: &NexusState, id: &str, f: impl FnOnce(&mut SwarmRecord)) -> Option<SwarmRecord> {
+    let mut swarms = state.active_swarms.lock().await;
+    let record = swarms.get_mut(id)?;
+    f(record);
+    Some(record.clone())
+}
+
+async fn is_cancelled(state: &NexusState, id: &str) -> bool {
+    state.active_swarms.lock().await
+        .get(id).map_or(true, |r| r.status == SwarmStatus::Cancelled)
+}
+
+/// Result of one attempt at running a nexus process
+struct AttemptOutcome {
+    output: String,
+    response: String,                   // Reply text parsed from `output`
+    error: Option<String>,
+}
+
+/// Run one nexus process in `dir` for the swarm's planner (`subtask` is
+/// `None`) or one of its subtasks. The PID is stored on the record so the
+/// process can be paused or cancelled, and it is terminated once `timeout`
+/// elapses.
+#[allow(clippy::too_many_arguments)]
+async fn run_attempt(
+    app: &tauri::AppHandle,
+    state: &NexusState,
+    id: &str,
+    dir: Option<&str>,
+    subtask: Option<&str>,
+    prompt: &str,
+    model: Option<&str>,
+    timeout: Duration,
+) -> AttemptOutcome {
+    let creds = state.ssh_credentials.lock().await.clone();
+    let set_pid = |r: &mut SwarmRecord, pid: Option<u32>| match subtask {
+        Some(sub) => {
+            if let Some(s) = r.subtasks.iter_mut().find(|s| s.id == sub) {
+                s.pid = pid;
+            }
+        }
+        None => r.pid = pid,
+    };
+
+    // Spend is charged to the subtask's agent, or to the architect for planning
+    let worker = match subtask {
+        Some(sub) => state.active_swarms.lock().await
+            .get(id)
+            .and_then(|r| r.subtasks.iter().find(|s| s.id == sub))
+            .map_or_else(|| "backend".to_string(), |s| s.agent_type.clone()),
+        None => "architect".to_string(),
+    };
+
+    let flags: Vec<String> = chat_flags(model, &[]).into_iter().map(shell_quote).collect();
+    let command = format!("nexus {} {}", flags.join(" "), shell_quote(prompt));
+    let start = std::time::Instant::now();
+    let mut events = process::spawn_lines(creds, dir, &command);
+
+    let mut output = String::new();
+    let mut streamed_cost: Option<f64> = None;      // Charged from usage events as they arrive
+    let mut exit_code = None;
+    let mut failure = None;
+    let mut pid = None;
+    let mut timed_out = false;
+    let mut deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let event = match tokio::time::timeout_at(deadline, events.recv()).await {
+            Ok(Some(event)) => event,
+            Ok(None) => break,
+            Err(_) if !timed_out => {
+                timed_out = true;
+                if let Some(pid) = pid {
+                    let _ = terminate(&[pid], state).await;
+                }
+                // Give the process a moment to exit and flush its output
+                deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+                continue;
+            }
+            Err(_) => break,
+        };
+        match event {
+            ProcessEvent::Started(started) => {
+                pid = Some(started);
+                let updated = update_swarm(state, id, |r| set_pid(r, Some(started))).await;
+                // Timed out, cancelled or paused before the PID was known
+                match updated.map(|r| r.status) {
+                    _ if timed_out => {
+                        let _ = terminate(&[started], state).await;
+                    }
+                    Some(SwarmStatus::Cancelled) => {
+                        let _ = terminate(&[started], state).await;
+                    }
+                    Some(SwarmStatus::Paused) => {
+                        let _ = signal_process(&[started], "STOP", state).await;
+                    }
+                    _ => {}
+                }
+            }
+            ProcessEvent::Line(line) => {
+                if let Some(event) = usage_event(&line) {
+                    let cost = event.cost.unwrap_or_else(|| {
+                        usage::cost_for(event.model.as_deref().or(model), event.tokens)
+                    });
+                    *streamed_cost.get_or_insert(0.0) += cost;
+                    let charged_to = event.worker_type.as_deref().unwrap_or(&worker);
+                    charge(app, state, id, charged_to, event.tokens, cost).await;
+                    continue;
+                }
+                // Only the planner's own progress events describe the swarm
+                let progress = match subtask {
+                    Some(_) => None,
+                    None => {
+                        let mut swarms = state.active_swarms.lock().await;
+                        swarms.get_mut(id).and_then(|r| apply_event(r, &line))
+                    }
+                };
+                match progress {
+                    Some(payload) => {
+                        let _ = app.emit("nexus://swarm-progress", payload);
+                    }
+                    None => {
+                        output.push_str(&line);
+                        output.push('\n');
+                    }
+                }
+            }
+            ProcessEvent::Exited(code) => exit_code = Some(code),
+            ProcessEvent::Failed(e) => failure = Some(e),
+        }
+    }
+    if timed_out && pid.is_none() {
+        // Still starting up: terminate it as soon as its PID arrives
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            while let Some(event) = events.recv().await {
+                if let ProcessEvent::Started(pid) = event {
+                    let _ = signal_process(&[pid], "TERM", &app.state::<NexusState>()).await;
+                    break;
+                }
+            }
+        });
+    }
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    let response = parse_chat_response(&output);
+    let mut usage = measure_usage(&output, prompt, &response, model, latency_ms, state).await;
+    // Streamed events were charged as they arrived; only charge what they missed
+    let (tokens, cost) = match streamed_cost {
+        Some(cost) => {
+            let measured = usage.cost;
+            usage.cost = cost.max(measured);
+            usage.estimated = false;
+            (0, usage.cost - cost)
+        }
+        None => (usage.prompt_tokens + usage.completion_tokens, usage.cost),
+    };
+    if tokens > 0 || cost > 0.0 {
+        charge(app, state, id, &worker, tokens, cost).await;
+    }
+    let project = update_swarm(state, id, |r| set_pid(r, None)).await.and_then(|r| r.project);
+    record_usage(UsageRecord {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        source: "swarm".to_string(),
+        conversation_id: None,
+        message_id: Some(id.to_string()),
+        project,
+        usage,
+    }, app, state).await;
+
+    let reported_failure = serde_json::from_str::<serde_json::Value>(&output)
+        .ok()
+        .filter(|json| json["success"].as_bool() == Some(false))
+        .map(|_| response.clone());
+    let error = if timed_out {
+        Some(format!("Timed out after {}s", timeout.as_secs()))
+    } else {
+        failure
+            .or_else(|| match exit_code {
+                Some(0) | None => None,
+                Some(code) => Some(format!("nexus exited with status {}", code)),
+            })
+            .or(reported_failure)
+    };
+    AttemptOutcome { output, response, error }
+}
+
+/// `run_attempt`, retrying failures with exponential backoff
+async fn run_with_retries(
+    app: &tauri::AppHandle,
+    state: &NexusState,
+    id: &str,
+    dir: Option<&str>,
+    subtask: Option<&str>,
+    prompt: &str,
+    config: &SwarmConfig,
+) -> AttemptOutcome {
+    let timeout = Duration::from_secs(config.task_timeout_secs.max(1));
+    let model = match subtask {
+        Some(_) => None,
+        None => config.planner_model.as_deref(),
+    };
+    let mut retry = 0;
+    loop {
+        let outcome = run_attempt(app, state, id, dir, subtask, prompt, model, timeout).await;
+        if outcome.error.is_none() || retry >= config.max_retries || is_cancelled(state, id).await {
+            return outcome;
+        }
+
+        retry += 1;
+        let backoff = Duration::from_secs(RETRY_BACKOFF_SECS << (retry - 1).min(6));
+        eprintln!("[Tauri] Swarm {} {} failed ({}), retry {} in {}s",
+            id, subtask.unwrap_or("planner"), outcome.error.as_deref().unwrap_or(""), retry, backoff.as_secs());
+        tokio::time::sleep(backoff).await;
+        if is_cancelled(state, id).await {
+            return outcome;
+        }
+        let updated = update_swarm(state, id, |r| {
+            r.attempts += 1;
+            // Drop the plan a failed planner attempt streamed in
+            if subtask.is_none() {
+                r.subtasks.clear();
+                r.workers.clear();
+                r.progress = 0;
+            }
+        }).await;
+        if let Some(record) = updated {
+            emit_progress(app, &record);
+        }
+    }
+}
+
+/// Recompute overall progress from the share of finished subtasks
+fn update_progress(record: &mut SwarmRecord) {
+    if record.subtasks.is_empty() {
+        return;
+    }
+    let done = record.subtasks.iter()
+        .filter(|s| matches!(s.status, TaskStatus::Completed | TaskStatus::Failed | TaskStatus::Cancelled))
+        .count();
+    record.progress = (done * 100 / record.subtasks.len()) as u8;
+}
+
+/// Apply `f` to the worker for `agent_type`, creating it on first use
+fn update_worker(record: &mut SwarmRecord, agent_type: &str, f: impl FnOnce(&mut WorkerInfo)) -> WorkerInfo {
+    let index = match record.workers.iter().position(|w| w.worker_type == agent_type) {
+        Some(i) => i,
+        None => {
+            record.workers.push(WorkerInfo {
+                worker_type: agent_type.to_string(),
+                name: agent_type.to_string(),
+                status: "idle".to_string(),
+                current_task: None,
+                progress: 0,
+                last_result: None,
+                files_modified: Vec::new(),
+                tokens_used: 0,
+                cost: 0.0,
+            });
+            record.workers.len() - 1
+        }
+    };
+    f(&mut record.workers[index]);
+    record.workers[index].clone()
+}
+
+/// Mark the subtasks that can run now as in progress, up to `slots` of
+/// them, and return each with its prompt
+fn launch_subtasks(app: &tauri::AppHandle, record: &mut SwarmRecord, slots: usize) -> Vec<(String, String)> {
+    let mut launched = Vec::new();
+    for index in planner::ready(&record.subtasks).into_iter().take(slots) {
+        record.subtasks[index].status = TaskStatus::InProgress;
+        let subtask = record.subtasks[index].clone();
+        let worker = update_worker(record, &subtask.agent_type, |w| {
+            w.status = "working".to_string();
+            w.current_task = Some(subtask.description.clone());
+            w.progress = 0;
+        });
+        let _ = app.emit("nexus://swarm-progress", progress_payload(record, Some(&subtask), Some(&worker)));
+        launched.push((subtask.id.clone(), planner::subtask_prompt(&record.description, &subtask, &record.subtasks)));
+    }
+    launched
+}
+
+/// Record a finished subtask. A failure skips everything downstream of it.
+fn finish_subtask(app: &tauri::AppHandle, record: &mut SwarmRecord, id: &str, outcome: &AttemptOutcome, elapsed: Duration) {
+    let Some(index) = record.subtasks.iter().position(|s| s.id == id) else {
+        return;
+    };
+    let cancelled = record.status == SwarmStatus::Cancelled;
+    let subtask = &mut record.subtasks[index];
+    subtask.status = match (&outcome.error, cancelled) {
+        (None, _) => TaskStatus::Completed,
+        (Some(_), true) => TaskStatus::Cancelled,
+        (Some(_), false) => TaskStatus::Failed,
+    };
+    subtask.output = Some(match &outcome.error {
+        Some(error) if outcome.response.is_empty() => error.clone(),
+        _ => outcome.response.clone(),
+    });
+    subtask.execution_time_ms = Some(elapsed.as_millis() as u64);
+    let subtask = subtask.clone();
+
+    if subtask.status == TaskStatus::Failed {
+        for blocked in planner::dependents(&record.subtasks, id) {
+            if let Some(s) = record.subtasks.iter_mut().find(|s| s.id == blocked && s.status == TaskStatus::Pending) {
+                s.status = TaskStatus::Cancelled;
+                s.output = Some(format!("Skipped: dependency {} failed", id));
+            }
+        }
+    }
+    update_progress(record);
+
+    let busy = record.subtasks.iter()
+        .any(|s| s.agent_type == subtask.agent_type && s.status == TaskStatus::InProgress);
+    let worker = update_worker(record, &subtask.agent_type, |w| {
+        if !busy {
+            w.status = if subtask.status == TaskStatus::Completed { "completed" } else { "error" }.to_string();
+            w.current_task = None;
+            w.progress = 100;
+        }
+        w.last_result = subtask.output.as_ref().map(|o| o.chars().take(200).collect());
+    });
+    let _ = app.emit("nexus://swarm-progress", progress_payload(record, Some(&subtask), Some(&worker)));
+}
+
+/// What a finished subtask changed
+#[derive(Default)]
+struct SubtaskChanges {
+    diff: Option<(String, String)>,     // Snapshots bracketing the subtask's changes
+    files: Vec<String>,
+    conflicts: Vec<MergeConflict>,
+    snapshot: Option<String>,           // The project afterwards, for later worktrees
+}
+
+/// Gather a finished subtask's changes. A subtask run in a worktree is
+/// merged into the project first, unless it failed.
+#[allow(clippy::too_many_arguments)]
+async fn collect_changes(
+    state: &NexusState,
+    id: &str,
+    project: &str,
+    subtask: &str,
+    worktree: Option<worktree::Worktree>,
+    outcome: &AttemptOutcome,
+    last_snapshot: &str,
+    auto_merge: bool,
+) -> SubtaskChanges {
+    let Some(worktree) = worktree else {
+        // Shared checkout: subtasks running side by side share a window
+        // between snapshots, so the first to finish gets both their changes
+        let Ok(to) = review::snapshot(project, id, subtask, state).await else {
+            return SubtaskChanges::default();
+        };
+        return SubtaskChanges {
+            files: review::changed_files(project, last_snapshot, &to, state).await.unwrap_or_default(),
+            diff: Some((last_snapshot.to_string(), to.clone())),
+            conflicts: Vec::new(),
+            snapshot: Some(to),
+        };
+    };
+
+    let mut changes = SubtaskChanges::default();
+    if outcome.error.is_none() {
+        let merged = async {
+            let head = worktree::commit(&worktree, &format!("nexus swarm {} subtask {}", id, subtask), state).await?;
+            let record = state.active_swarms.lock().await
+                .get(id).cloned()
+                .ok_or("Swarm record is gone")?;
+            let (files, conflicts) = worktree::merge_back(project, &worktree, &head, subtask, auto_merge, &record, state).await?;
+            let after = review::snapshot(project, id, subtask, state).await?;
+            Ok::<_, String>(SubtaskChanges {
+                diff: Some((worktree.start.clone(), head)),
+                files,
+                conflicts,
+                snapshot: Some(after),
+            })
+        }.await;
+        match merged {
+            Ok(merged) => changes = merged,
+            Err(e) => eprintln!("[Tauri] Swarm {} failed to merge subtask {}: {}", id, subtask, e),
+        }
+    }
+
+    // Keep the branch when a conflict needs a person to look at it
+    let keep_branch = changes.conflicts.iter().any(|c| c.resolution == "manual_required");
+    if let Err(e) = worktree::remove(project, &worktree, keep_branch, state).await {
+        eprintln!("[Tauri] Swarm {} failed to remove worktree {}: {}", id, worktree.root, e);
+    }
+    changes
+}
+
+/// Plan the swarm, then run its subtasks as their dependencies complete,
+/// up to `max_parallel_subtasks` at a time, feeding each the outputs of
+/// the subtasks it depends on
+async fn execute_plan(app: &tauri::AppHandle, state: &NexusState, record: &SwarmRecord, config: &SwarmConfig) -> AttemptOutcome {
+    let id = record.id.as_str();
+    let project = record.project.as_deref();
+    let plan = run_with_retries(app, state, id, project, None, &planner::plan_prompt(&record.description), config).await;
+    if let Some(error) = plan.error {
+        return AttemptOutcome { error: Some(format!("Planning failed: {}", error)), ..plan };
+    }
+    if is_cancelled(state, id).await {
+        return plan;
+    }
+
+    // Prefer a plan in the reply, then subtasks the CLI streamed while planning
+    let streamed = state.active_swarms.lock().await
+        .get(id).map(|r| r.subtasks.clone()).unwrap_or_default();
+    let subtasks = match planner::parse_plan(&plan.response) {
+        Some(subtasks) => subtasks,
+        None if !streamed.is_empty() => planner::reset(streamed),
+        None => {
+            eprintln!("[Tauri] Swarm {} planner gave no plan, running it as one task", id);
+            planner::single(&record.description)
+        }
+    };
+    if let Err(e) = planner::validate(&subtasks) {
+        return AttemptOutcome { error: Some(format!("Invalid plan: {}", e)), ..plan };
+    }
+
+    // Each subtask runs in a worktree started from the latest snapshot, so it
+    // sees the merged work of its dependencies. Without git, subtasks share
+    // the project directory.
+    let mut last_snapshot = match project {
+        Some(project) => review::snapshot(project, id, "base", state).await
+            .map_err(|e| eprintln!("[Tauri] Swarm {} runs without diffs: {}", id, e))
+            .ok(),
+        None => None,
+    };
+    if let Some(record) = update_swarm(state, id, |r| {
+        r.subtasks = subtasks;
+        r.workers.clear();
+        r.progress = 0;
+        r.base_snapshot = last_snapshot.clone();
+    }).await {
+        emit_progress(app, &record);
+    }
+
+    let cap = config.max_parallel_subtasks.max(1);
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut running = 0;
+    loop {
+        let (status, launched) = {
+            let mut swarms = state.active_swarms.lock().await;
+            let Some(r) = swarms.get_mut(id) else { break };
+            let launched = match r.status {
+                SwarmStatus::Running => launch_subtasks(app, r, cap.saturating_sub(running)),
+                _ => Vec::new(),
+            };
+            (r.status, launched)
+        };
+        for (subtask, prompt) in launched {
+            running += 1;
+            let worktree = match (project, &last_snapshot) {
+                (Some(project), Some(start)) => worktree::create(project, id, &subtask, start, state).await
+                    .map_err(|e| eprintln!("[Tauri] Swarm {} subtask {} runs in the project: {}", id, subtask, e))
+                    .ok(),
+                _ => None,
+            };
+            let dir = worktree.as_ref().map(|w| w.dir.clone()).or_else(|| record.project.clone());
+            let (app, tx, config, id) = (app.clone(), tx.clone(), config.clone(), id.to_string());
+            tauri::async_runtime::spawn(async move {
+                let state = app.state::<NexusState>();
+                let start = std::time::Instant::now();
+                let outcome = run_with_retries(&app, &state, &id, dir.as_deref(), Some(&subtask), &prompt, &config).await;
+                let _ = tx.send((subtask, worktree, outcome, start.elapsed()));
+            });
+        }
+
+        if running == 0 {
+            if status == SwarmStatus::Paused {
+                // Wait for resume before starting the next subtasks
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+            break;
+        }
+        let Some((subtask, worktree, outcome, elapsed)) = rx.recv().await else { break };
+        running -= 1;
+        let changes = match (project, &last_snapshot) {
+            (Some(project), Some(from)) => {
+                collect_changes(state, id, project, &subtask, worktree, &outcome, from, config.auto_merge).await
+            }
+            _ => SubtaskChanges::default(),
+        };
+        if changes.snapshot.is_some() {
+            last_snapshot = changes.snapshot;
+        }
+
+        let mut swarms = state.active_swarms.lock().await;
+        if let Some(r) = swarms.get_mut(id) {
+            if let Some(s) = r.subtasks.iter_mut().find(|s| s.id == subtask) {
+                if let Some((from, to)) = changes.diff {
+                    s.diff_from = Some(from);
+                    s.diff_to = Some(to);
+                }
+                merge_files(&mut s.files_modified, changes.files);
+            }
+            for conflict in &changes.conflicts {
+                let _ = app.emit("nexus://swarm-conflict", serde_json::json!({
+                    "taskId": id,
+                    "subtaskId": subtask,
+                    "conflict": conflict,
+                }));
+            }
+            r.conflicts.extend(changes.conflicts);
+            finish_subtask(app, r, &subtask, &outcome, elapsed);
+        }
+    }
+
+    update_swarm(state, id, |r| r.final_snapshot = last_snapshot.clone()).await;
+
+    let subtasks = state.active_swarms.lock().await
+        .get(id).map(|r| r.subtasks.clone()).unwrap_or_default();
+    let failed: Vec<&str> = subtasks.iter()
+        .filter(|s| s.status == TaskStatus::Failed)
+        .map(|s| s.id.as_str())
+        .collect();
+    AttemptOutcome {
+        output: planner::combined_output(&subtasks),
+        response: String::new(),
+        error: (!failed.is_empty()).then(|| format!("Subtasks failed: {}", failed.join(", "))),
+    }
+}
+
+/// Native notification for a swarm that completed or failed; cancelled
+/// swarms were stopped by the user and stay quiet
+async fn notify_finished(app: &tauri::AppHandle, record: &SwarmRecord) {
+    let (success, title) = match record.status {
+        SwarmStatus::Completed => (true, "Swarm completed"),
+        SwarmStatus::Failed => (false, "Swarm failed"),
+        _ => return,
+    };
+    let done = record.subtasks.iter().filter(|s| s.status == TaskStatus::Completed).count();
+    let mut body = format!("{}\n{} of {} subtasks done", notifications::summary(&record.description), done, record.subtasks.len());
+    if let Some(error) = &record.error {
+        body.push_str(&format!(" - {}", notifications::summary(error)));
+    }
+    if !record.conflicts.is_empty() {
+        body.push_str(&format!(", {} merge conflicts", record.conflicts.len()));
+    }
+    notifications::notify(app, NotifyEvent::Swarm, success, title, &body, FocusTarget {
+        kind: "swarm".to_string(),
+        id: Some(record.id.clone()),
+    }).await;
+}
+
+/// Drive one swarm that the scheduler marked running to a finished state
+async fn run_swarm(app: tauri::AppHandle, id: String) {
+    let state = app.state::<NexusState>();
+    let Some(record) = state.active_swarms.lock().await.get(&id).cloned() else {
+        return;
+    };
+    // Cancelled between scheduling and start
+    if record.status != SwarmStatus::Running {
+        state.swarm_wakeup.notify_one();
+        return;
+    }
+    emit_progress(&app, &record);
+
+    let config = state.swarm_config.lock().await.clone();
+    let outcome = execute_plan(&app, &state, &record, &config).await;
+
+    let finished = update_swarm(&state, &id, |r| {
+        if r.status == SwarmStatus::Cancelled {
+            // Keep what was produced before the cancel as a partial result
+            for subtask in r.subtasks.iter_mut().filter(|s| !matches!(s.status, TaskStatus::Completed | TaskStatus::Failed)) {
+                subtask.status = TaskStatus::Cancelled;
+            }
+        } else {
+            r.status = if outcome.error.is_some() { SwarmStatus::Failed } else { SwarmStatus::Completed };
+        }
+        if r.status == SwarmStatus::Completed {
+            r.progress = 100;
+        }
+        r.completed_at = Some(chrono::Utc::now().to_rfc3339());
+        r.pid = None;
+        r.output = outcome.output;
+        // A reason given when cancelling takes precedence
+        r.error = r.error.take().or(outcome.error);
+        r.result = Some(r.build_result());
+    }).await;
+    if let Some(record) = finished {
+        persist(&app, &record);
+        emit_progress(&app, &record);
+        notify_finished(&app, &record).await;
+    }
+    history::prune(&app, &state).await;
+
+    // A slot is free again
+    state.swarm_wakeup.notify_one();
+}
+
+/// Start queued swarms while fewer than `max_concurrent_workers` are active
+async fn launch_ready(app: &tauri::AppHandle, state: &NexusState) {
+    let cap = state.swarm_config.lock().await.max_concurrent_workers.max(1);
+    let mut queue = state.swarm_queue.lock().await;
+    let mut swarms = state.active_swarms.lock().await;
+    let mut active = swarms.values()
+        .filter(|r| matches!(r.status, SwarmStatus::Running | SwarmStatus::Paused))
+        .count();
+
+    while active < cap {
+        let Some(id) = queue.pop_front() else { break };
+        let Some(record) = swarms.get_mut(&id) else { continue };
+        if record.status != SwarmStatus::Queued {
+            continue;
+        }
+        record.status = SwarmStatus::Running;
+        record.started_at = Some(chrono::Utc::now().to_rfc3339());
+        active += 1;
+        tauri::async_runtime::spawn(run_swarm(app.clone(), id));
+    }
+}
+
+/// Long-lived scheduler task, woken whenever a swarm is queued or finishes
+pub async fn scheduler(app: tauri::AppHandle) {
+    let state = app.state::<NexusState>();
+    loop {
+        state.swarm_wakeup.notified().await;
+        launch_ready(&app, &state).await;
+    }
+}
+
+// ============================================================================
+// Persistence
+// ============================================================================
+
+fn config_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir.join("swarm_config.json"))
+}
+
+pub fn load_config(app: &tauri::AppHandle) -> SwarmConfig {
+    config_path(app)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_config(app: &tauri::AppHandle, config: &SwarmConfig) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+    std::fs::write(config_path(app)?, json).map_err(|e| e.to_string())
+}
+
+/// Create a swarm record for `description`, save it and put it in the queue
+pub(crate) async fn queue_swarm(
+    description: String,
+    project: Option<String>,
+    cost_limit: Option<f64>,
+    app: &tauri::AppHandle,
+    state: &NexusState,
+) -> String {
+    let record = SwarmRecord::new(description, project, cost_limit);
+    let task_id = record.id.clone();
+    persist(app, &record);
+    state.active_swarms.lock().await.insert(task_id.clone(), record);
+    state.swarm_queue.lock().await.push_back(task_id.clone());
+    state.swarm_wakeup.notify_one();
+    task_id
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Queue a swarm task; the scheduler starts it once a slot is free. Returns
+/// the task id immediately; poll `get_swarm_status` for progress.
+#[tauri::command]
+pub async fn start_swarm_task(
+    task: String,
+    override_budget: Option<bool>,
+    cost_limit: Option<f64>,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<String, String> {
+    enforce_budget(&task, None, override_budget.unwrap_or(false), &state).await?;
+    if cost_limit.is_some_and(|limit| limit <= 0.0) {
+        return Err("costLimit must be greater than zero".into());
+    }
+
+    let project = state.current_project.lock().await
+        .as_ref().map(|p| p.to_string_lossy().to_string());
+    let cost_limit = match cost_limit {
+        Some(limit) => Some(limit),
+        None => state.swarm_config.lock().await.default_cost_limit,
+    };
+    let task_id = queue_swarm(task, project, cost_limit, &app, &state).await;
+
+    Ok(serde_json::json!({
+        "task_id": task_id,
+        "status": SwarmStatus::Queued,
+    }).to_string())
+}
+
+#[tauri::command]
+pub async fn get_swarm_status(id: String, state: State<'_, NexusState>) -> Result<String, String> {
+    let swarms = state.active_swarms.lock().await;
+    match swarms.get(&id) {
+        Some(record) => serde_json::to_string(record).map_err(|e| e.to_string()),
+        None => Ok(serde_json::json!({
+            "id": id,
+            "status": "not_found",
+        }).to_string()),
+    }
+}
+
+/// All known swarms, oldest first, with queue positions for those waiting
+#[tauri::command]
+pub async fn get_all_swarms(state: State<'_, NexusState>) -> Result<Vec<SwarmSummary>, String> {
+    let queue = state.swarm_queue.lock().await;
+    let swarms = state.active_swarms.lock().await;
+    let mut summaries: Vec<SwarmSummary> = swarms.values()
+        .map(|record| SwarmSummary {
+            queue_position: queue.iter().position(|id| *id == record.id),
+            record: record.clone(),
+        })
+        .collect();
+    summaries.sort_by(|a, b| a.record.created_at.cmp(&b.record.created_at));
+    Ok(summaries)
+}
+
+#[tauri::command]
+pub async fn get_swarm_config(state: State<'_, NexusState>) -> Result<SwarmConfig, String> {
+    Ok(state.swarm_config.lock().await.clone())
+}
+
+#[tauri::command]
+pub async fn set_swarm_config(config: SwarmConfig, app: tauri::AppHandle, state: State<'_, NexusState>) -> Result<(), String> {
+    if config.max_concurrent_workers == 0 {
+        return Err("maxConcurrentWorkers must be at least 1".into());
+    }
+    if config.max_parallel_subtasks == 0 {
+        return Err("maxParallelSubtasks must be at least 1".into());
+    }
+    save_config(&app, &config)?;
+    *state.swarm_config.lock().await = config;
+    // A higher cap may let queued swarms start now
+    state.swarm_wakeup.notify_one();
+    Ok(())
+}
+
+/// Stop a queued, running or paused swarm. Output produced so far is kept
+/// on the record as a partial result.
+#[tauri::command]
+pub async fn cancel_swarm(id: String, app: tauri::AppHandle, state: State<'_, NexusState>) -> Result<(), String> {
+    cancel(&app, &state, &id, None).await
+}
+
+/// Cancel a swarm, recording `reason` as its error when given
+async fn cancel(app: &tauri::AppHandle, state: &NexusState, id: &str, reason: Option<String>) -> Result<(), String> {
+    let (previous, record) = {
+        let mut swarms = state.active_swarms.lock().await;
+        let record = swarms.get_mut(id).ok_or_else(|| format!("Swarm not found: {}", id))?;
+        if record.status.is_finished() {
+            return Err("Swarm has already finished".into());
+        }
+        let previous = record.status;
+        record.status = SwarmStatus::Cancelled;
+        record.error = reason;
+        if previous == SwarmStatus::Queued {
+            record.completed_at = Some(chrono::Utc::now().to_rfc3339());
+            record.result = Some(record.build_result());
+        }
+        (previous, record.clone())
+    };
+    if previous == SwarmStatus::Queued {
+        state.swarm_queue.lock().await.retain(|queued| *queued != id);
+        persist(app, &record);
+    }
+
+    let pids = record.pids();
+    if !pids.is_empty() {
+        terminate(&pids, state).await?;
+    }
+    emit_progress(app, &record);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn pause_swarm(id: String, app: tauri::AppHandle, state: State<'_, NexusState>) -> Result<(), String> {
+    let pids = {
+        let swarms = state.active_swarms.lock().await;
+        let record = swarms.get(&id).ok_or_else(|| format!("Swarm not found: {}", id))?;
+        if record.status != SwarmStatus::Running {
+            return Err("Only running swarms can be paused".into());
+        }
+        record.pids()
+    };
+
+    // Between subtasks nothing is running; the paused status alone keeps
+    // the next one from starting
+    if !pids.is_empty() {
+        signal_process(&pids, "STOP", &state).await?;
+    }
+    if let Some(record) = update_swarm(&state, &id, |r| r.status = SwarmStatus::Paused).await {
+        emit_progress(&app, &record);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn resume_swarm(id: String, app: tauri::AppHandle, state: State<'_, NexusState>) -> Result<(), String> {
+    let pids = {
+        let swarms = state.active_swarms.lock().await;
+        let record = swarms.get(&id).ok_or_else(|| format!("Swarm not found: {}", id))?;
+        if record.status != SwarmStatus::Paused {
+            return Err("Only paused swarms can be resumed".into());
+        }
+        record.pids()
+    };
+
+    if !pids.is_empty() {
+        signal_process(&pids, "CONT", &state).await?;
+    }
+    if let Some(record) = update_swarm(&state, &id, |r| r.status = SwarmStatus::Running).await {
+        emit_progress(&app, &record);
+    }
+    Ok(())
+}
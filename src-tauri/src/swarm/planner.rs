@@ -0,0 +1,276 @@
+// Swarm planning - decompose a task into a dependency DAG of subtasks
+
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+use super::{Subtask, TaskStatus};
+
+/// Dependency outputs longer than this are cut before being fed forward
+const MAX_DEPENDENCY_OUTPUT_CHARS: usize = 8000;
+
+const AGENT_TYPES: [&str; 6] = ["architect", "frontend", "backend", "qa", "devops", "security"];
+
+/// One entry of the plan as returned by the CLI
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlannedSubtask {
+    id: String,
+    description: String,
+    #[serde(default, alias = "agent_type")]
+    agent_type: Option<String>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+/// Prompt asking the architect for a decomposition of `description`
+pub fn plan_prompt(description: &str) -> String {
+    format!(
+        "You are the architect of a team of agents ({agents}). Break the task below into \
+         subtasks that can each be done by one agent. Reply with only a JSON array of objects \
+         with the fields \"id\" (short unique string), \"description\", \"agentType\" (one of the \
+         agents above) and \"dependencies\" (ids of subtasks whose output this one needs). \
+         Subtasks without a dependency between them run in parallel, so only list real \
+         dependencies. Do not start the work itself.\n\nTask:\n{task}",
+        agents = AGENT_TYPES.join(", "),
+        task = description,
+    )
+}
+
+/// The first top-level JSON array in `text`, which may be wrapped in prose
+/// or a fenced code block
+fn extract_array(text: &str) -> Option<&str> {
+    let start = text.find('[')?;
+    let end = text.rfind(']')?;
+    (end > start).then(|| &text[start..=end])
+}
+
+/// Parse the planner's reply into pending subtasks; `None` when the reply
+/// holds no plan. The result still needs `validate`.
+pub fn parse_plan(response: &str) -> Option<Vec<Subtask>> {
+    let planned: Vec<PlannedSubtask> = serde_json::from_str(extract_array(response)?).ok()?;
+    if planned.is_empty() {
+        return None;
+    }
+
+    let subtasks = planned.into_iter()
+        .map(|p| {
+            let agent_type = p.agent_type
+                .map(|a| a.to_ascii_lowercase())
+                .filter(|a| AGENT_TYPES.contains(&a.as_str()))
+                .unwrap_or_else(|| "backend".to_string());
+            Subtask {
+                dependencies: p.dependencies,
+                ..Subtask::pending(p.id.trim(), &p.description, &agent_type)
+            }
+        })
+        .collect();
+    Some(subtasks)
+}
+
+/// Subtasks the CLI streamed while planning, reset so they run from scratch
+pub fn reset(mut subtasks: Vec<Subtask>) -> Vec<Subtask> {
+    for subtask in &mut subtasks {
+        subtask.status = TaskStatus::Pending;
+        subtask.output = None;
+        subtask.execution_time_ms = None;
+        subtask.files_modified.clear();
+    }
+    subtasks
+}
+
+/// Plan used when the planner gives nothing usable: the whole task as one subtask
+pub fn single(description: &str) -> Vec<Subtask> {
+    vec![Subtask::pending("task", description, "backend")]
+}
+
+/// Check ids are unique, dependencies exist, and the graph has no cycles
+pub fn validate(subtasks: &[Subtask]) -> Result<(), String> {
+    let mut ids = HashSet::new();
+    for subtask in subtasks {
+        if subtask.id.is_empty() {
+            return Err("Subtask with an empty id".into());
+        }
+        if !ids.insert(subtask.id.as_str()) {
+            return Err(format!("Duplicate subtask id: {}", subtask.id));
+        }
+    }
+    for subtask in subtasks {
+        if let Some(missing) = subtask.dependencies.iter().find(|d| !ids.contains(d.as_str())) {
+            return Err(format!("Subtask {} depends on unknown subtask {}", subtask.id, missing));
+        }
+    }
+
+    // Kahn's algorithm: anything left once no more nodes can be removed is on a cycle
+    let mut remaining: HashMap<&str, usize> = subtasks.iter()
+        .map(|s| (s.id.as_str(), s.dependencies.len()))
+        .collect();
+    let mut ready: Vec<&str> = remaining.iter()
+        .filter(|(_, deps)| **deps == 0)
+        .map(|(id, _)| *id)
+        .collect();
+    while let Some(done) = ready.pop() {
+        remaining.remove(done);
+        for subtask in subtasks.iter().filter(|s| s.dependencies.iter().any(|d| d == done)) {
+            if let Some(deps) = remaining.get_mut(subtask.id.as_str()) {
+                *deps -= subtask.dependencies.iter().filter(|d| *d == done).count();
+                if *deps == 0 {
+                    ready.push(subtask.id.as_str());
+                }
+            }
+        }
+    }
+    if !remaining.is_empty() {
+        let mut cycle: Vec<&str> = remaining.into_keys().collect();
+        cycle.sort();
+        return Err(format!("Plan has a dependency cycle between: {}", cycle.join(", ")));
+    }
+    Ok(())
+}
+
+/// Indices of pending subtasks whose dependencies have all completed
+pub fn ready(subtasks: &[Subtask]) -> Vec<usize> {
+    let completed: HashSet<&str> = subtasks.iter()
+        .filter(|s| s.status == TaskStatus::Completed)
+        .map(|s| s.id.as_str())
+        .collect();
+    subtasks.iter()
+        .enumerate()
+        .filter(|(_, s)| s.status == TaskStatus::Pending)
+        .filter(|(_, s)| s.dependencies.iter().all(|d| completed.contains(d.as_str())))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Ids of subtasks that depend on `failed`, directly or transitively
+pub fn dependents(subtasks: &[Subtask], failed: &str) -> Vec<String> {
+    let mut blocked: Vec<String> = vec![failed.to_string()];
+    let mut index = 0;
+    while index < blocked.len() {
+        let id = blocked[index].clone();
+        for subtask in subtasks.iter().filter(|s| s.dependencies.contains(&id)) {
+            if !blocked.contains(&subtask.id) {
+                blocked.push(subtask.id.clone());
+            }
+        }
+        index += 1;
+    }
+    blocked.remove(0);
+    blocked
+}
+
+/// Prompt for one subtask, carrying the overall goal and the outputs of the
+/// subtasks it depends on
+pub fn subtask_prompt(goal: &str, subtask: &Subtask, subtasks: &[Subtask]) -> String {
+    let mut prompt = format!(
+        "You are the {} agent on a team working on this task:\n{}\n\nYour part:\n{}",
+        subtask.agent_type, goal, subtask.description
+    );
+    for dep in subtasks.iter().filter(|s| subtask.dependencies.contains(&s.id)) {
+        let output = dep.output.as_deref().unwrap_or("");
+        let truncated: String = output.chars().take(MAX_DEPENDENCY_OUTPUT_CHARS).collect();
+        prompt.push_str(&format!("\n\n### Output of {} ({})\n{}", dep.id, dep.description, truncated));
+        if truncated.len() < output.len() {
+            prompt.push_str("\n[truncated]");
+        }
+    }
+    prompt
+}
+
+/// Final output of a finished plan: the outputs of subtasks nothing depends on
+pub fn combined_output(subtasks: &[Subtask]) -> String {
+    subtasks.iter()
+        .filter(|s| !subtasks.iter().any(|other| other.dependencies.contains(&s.id)))
+        .filter_map(|s| s.output.as_deref().map(|output| (s, output)))
+        .map(|(s, output)| if subtasks.len() == 1 {
+            output.to_string()
+        } else {
+            format!("## {} ({})\n{}", s.id, s.agent_type, output)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(edges: &[(&str, &[&str])]) -> Vec<Subtask> {
+        edges.iter()
+            .map(|(id, deps)| Subtask {
+                dependencies: deps.iter().map(|d| d.to_string()).collect(),
+                ..Subtask::pending(id, id, "backend")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn validate_accepts_a_dag() {
+        assert_eq!(validate(&plan(&[("a", &[]), ("b", &["a"]), ("c", &["a", "b"])])), Ok(()));
+    }
+
+    #[test]
+    fn validate_rejects_cycles() {
+        let error = validate(&plan(&[("a", &["c"]), ("b", &["a"]), ("c", &["b"]), ("d", &[])])).unwrap_err();
+        assert_eq!(error, "Plan has a dependency cycle between: a, b, c");
+    }
+
+    #[test]
+    fn validate_rejects_unknown_and_self_dependencies() {
+        let error = validate(&plan(&[("a", &[]), ("b", &["z"])])).unwrap_err();
+        assert_eq!(error, "Subtask b depends on unknown subtask z");
+        let error = validate(&plan(&[("a", &["a"])])).unwrap_err();
+        assert!(error.contains("cycle"), "{}", error);
+    }
+
+    #[test]
+    fn validate_rejects_duplicate_and_empty_ids() {
+        assert_eq!(validate(&plan(&[("a", &[]), ("a", &[])])), Err("Duplicate subtask id: a".to_string()));
+        assert!(validate(&plan(&[("", &[])])).is_err());
+    }
+
+    #[test]
+    fn ready_keeps_plan_order_and_waits_for_dependencies() {
+        let mut subtasks = plan(&[("c", &["a"]), ("a", &[]), ("b", &[]), ("d", &["a", "b"])]);
+        assert_eq!(ready(&subtasks), [1, 2]);
+
+        subtasks[1].status = TaskStatus::Completed;
+        subtasks[2].status = TaskStatus::InProgress;
+        assert_eq!(ready(&subtasks), [0]);
+
+        subtasks[2].status = TaskStatus::Completed;
+        assert_eq!(ready(&subtasks), [0, 3]);
+    }
+
+    #[test]
+    fn dependents_are_found_transitively_once() {
+        let subtasks = plan(&[("a", &[]), ("b", &["a"]), ("c", &["b"]), ("d", &["a", "c"]), ("e", &[])]);
+        assert_eq!(dependents(&subtasks, "a"), ["b", "d", "c"]);
+        assert!(dependents(&subtasks, "e").is_empty());
+    }
+
+    #[test]
+    fn parse_plan_reads_json_wrapped_in_prose() {
+        let reply = "Here is the plan:\n```json\n[\
+            {\"id\": \" api \", \"description\": \"Build the API\", \"agentType\": \"Backend\"},\
+            {\"id\": \"ui\", \"description\": \"Build the UI\", \"agent_type\": \"frontend\", \"dependencies\": [\"api\"]},\
+            {\"id\": \"docs\", \"description\": \"Write docs\", \"agentType\": \"writer\"}\
+        ]\n```\nLet me know if this works.";
+        assert!(extract_array(reply).is_some_and(|a| a.starts_with('[') && a.ends_with(']')));
+
+        let subtasks = parse_plan(reply).unwrap();
+        let summary: Vec<(&str, &str, usize)> = subtasks.iter()
+            .map(|s| (s.id.as_str(), s.agent_type.as_str(), s.dependencies.len()))
+            .collect();
+        // Unknown agent types fall back to backend
+        assert_eq!(summary, [("api", "backend", 0), ("ui", "frontend", 1), ("docs", "backend", 0)]);
+        assert_eq!(subtasks[0].status, TaskStatus::Pending);
+    }
+
+    #[test]
+    fn parse_plan_rejects_replies_without_a_plan() {
+        assert!(parse_plan("I could not come up with a plan.").is_none());
+        assert!(parse_plan("[]").is_none());
+        assert!(parse_plan("] backwards [").is_none());
+        assert!(parse_plan("[{\"description\": \"no id\"}]").is_none());
+    }
+}
@@ -0,0 +1,408 @@
+// Swarm review - snapshot the project with git while a swarm runs, diff
+// what each subtask changed, and accept, reject or roll back those changes
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use super::{emit_progress, persist, update_swarm, SwarmRecord};
+use crate::git::{header_paths, marker_path};
+use crate::{execute_shell_checked, shell_quote, NexusState};
+
+/// Identity for snapshot commits, so they work without a configured git user
+pub(super) const SNAPSHOT_IDENTITY: &str = "GIT_AUTHOR_NAME=nexus GIT_AUTHOR_EMAIL=nexus@localhost \
+    GIT_COMMITTER_NAME=nexus GIT_COMMITTER_EMAIL=nexus@localhost";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewDecision {
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    pub index: usize,
+    pub header: String,                 // The "@@ -a,b +c,d @@" line
+    pub patch: String,                  // Header and body lines
+    pub decision: ReviewDecision,
+    #[serde(skip)]
+    origin: Option<usize>,              // Index of the same change in the swarm's own diff
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDiff {
+    pub path: String,
+    pub status: String,                 // added, modified or deleted
+    pub binary: bool,
+    pub hunks: Vec<DiffHunk>,
+    pub decision: ReviewDecision,
+    #[serde(skip)]
+    header: String,                     // "diff --git" through "+++", for rebuilding patches
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubtaskDiff {
+    pub subtask_id: String,
+    pub files: Vec<FileDiff>,
+}
+
+/// Changes a swarm made, as returned by `get_swarm_diff`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwarmDiff {
+    pub task_id: String,
+    pub base: String,                   // Snapshot taken before the first subtask
+    pub files: Vec<FileDiff>,           // Base against the project as it is now
+    pub subtasks: Vec<SubtaskDiff>,
+}
+
+/// Key in `SwarmRecord::review` for a file or one of its hunks. Hunks are
+/// numbered as in the diff from the base to the final snapshot, which
+/// doesn't move when other hunks are rejected in the project.
+fn review_key(path: &str, hunk: Option<usize>) -> String {
+    match hunk {
+        Some(index) => format!("{}@@{}", path, index),
+        None => path.to_string(),
+    }
+}
+
+/// `cd` into the top level of the project's repository
+pub(super) fn cd_toplevel(project: &str) -> String {
+    format!("cd {} && cd \"$(git rev-parse --show-toplevel)\"", shell_quote(project))
+}
+
+/// Record the whole working tree, untracked files included, as a commit
+/// pinned under `refs/nexus/swarms/<id>/<label>`. Uses a scratch index so
+/// the project's own index is untouched.
+pub async fn snapshot(project: &str, id: &str, label: &str, state: &NexusState) -> Result<String, String> {
+    let label: String = label.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect();
+    let script = format!(
+        "{cd} && tmp=$(mktemp) && {{ cp \"$(git rev-parse --git-path index)\" \"$tmp\" 2>/dev/null || true; }} \
+         && GIT_INDEX_FILE=\"$tmp\" git add -A && tree=$(GIT_INDEX_FILE=\"$tmp\" git write-tree) && rm -f \"$tmp\" \
+         && commit=$({identity} git commit-tree \"$tree\" -m {message}) \
+         && git update-ref {reference} \"$commit\" && echo \"$commit\"",
+        cd = cd_toplevel(project),
+        identity = SNAPSHOT_IDENTITY,
+        message = shell_quote(&format!("nexus swarm {} {}", id, label)),
+        reference = shell_quote(&format!("refs/nexus/swarms/{}/{}", id, label)),
+    );
+    let commit = execute_shell_checked(&script, state).await?.trim().to_string();
+    if commit.is_empty() {
+        return Err("git did not report a snapshot commit".into());
+    }
+    Ok(commit)
+}
+
+/// Delete the snapshot refs kept for a swarm
+pub async fn drop_snapshots(project: &str, id: &str, state: &NexusState) -> Result<(), String> {
+    let script = format!(
+        "{} && git for-each-ref --format='delete %(refname)' {} | git update-ref --stdin",
+        cd_toplevel(project),
+        shell_quote(&format!("refs/nexus/swarms/{}/", id)),
+    );
+    execute_shell_checked(&script, state).await.map(|_| ())
+}
+
+/// Paths that differ between two snapshots
+pub async fn changed_files(project: &str, from: &str, to: &str, state: &NexusState) -> Result<Vec<String>, String> {
+    let script = format!("{} && git diff --no-renames --name-only {} {}", cd_toplevel(project), from, to);
+    Ok(execute_shell_checked(&script, state).await?
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(|l| l.to_string())
+        .collect())
+}
+
+/// Split `git diff --no-renames` output into files and hunks
+fn parse_diff(diff: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    for line in diff.lines() {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            files.push(FileDiff {
+                path: header_paths(paths).1,
+                status: "modified".to_string(),
+                binary: false,
+                hunks: Vec::new(),
+                decision: ReviewDecision::Pending,
+                header: String::new(),
+            });
+        }
+        let Some(file) = files.last_mut() else { continue };
+        if line.starts_with("@@") {
+            file.hunks.push(DiffHunk {
+                index: file.hunks.len(),
+                header: line.to_string(),
+                patch: String::new(),
+                decision: ReviewDecision::Pending,
+                origin: None,
+            });
+        }
+        match file.hunks.last_mut() {
+            Some(hunk) => {
+                hunk.patch.push_str(line);
+                hunk.patch.push('\n');
+            }
+            None => {
+                if line.starts_with("new file mode") {
+                    file.status = "added".to_string();
+                } else if line.starts_with("deleted file mode") {
+                    file.status = "deleted".to_string();
+                } else if line.starts_with("Binary files") {
+                    file.binary = true;
+                } else if let Some(old) = line.strip_prefix("--- ").filter(|p| *p != "/dev/null") {
+                    // Exact, unlike the "diff --git" line
+                    file.path = marker_path(old, "a/");
+                } else if let Some(new) = line.strip_prefix("+++ ").filter(|p| *p != "/dev/null") {
+                    file.path = marker_path(new, "b/");
+                }
+                file.header.push_str(line);
+                file.header.push('\n');
+            }
+        }
+    }
+    files
+}
+
+/// The added and removed lines of a hunk, which stay the same when other
+/// hunks shift its line numbers
+fn changed_lines(hunk: &DiffHunk) -> Vec<&str> {
+    hunk.patch.lines()
+        .skip(1)
+        .filter(|l| l.starts_with('+') || l.starts_with('-'))
+        .collect()
+}
+
+/// Match each hunk to the same change in `reference` (the swarm's own
+/// diff) and fill in the decisions recorded for them
+fn apply_decisions(files: &mut [FileDiff], reference: &[FileDiff], record: &SwarmRecord) {
+    for file in files {
+        file.decision = record.review.get(&file.path).copied().unwrap_or(ReviewDecision::Pending);
+        let theirs = reference.iter()
+            .find(|f| f.path == file.path)
+            .map_or(&[][..], |f| &f.hunks[..]);
+        let mut used = vec![false; theirs.len()];
+        for hunk in &mut file.hunks {
+            let lines = changed_lines(hunk);
+            hunk.origin = theirs.iter()
+                .position(|h| !used[h.index] && changed_lines(h) == lines);
+            if let Some(index) = hunk.origin {
+                used[index] = true;
+            }
+            hunk.decision = hunk.origin
+                .and_then(|index| record.review.get(&review_key(&file.path, Some(index))))
+                .copied()
+                .unwrap_or(file.decision);
+        }
+    }
+}
+
+async fn diff_between(project: &str, from: &str, to: &str, state: &NexusState) -> Result<Vec<FileDiff>, String> {
+    let script = format!("{} && git diff --no-renames --no-color {} {}", cd_toplevel(project), from, to);
+    Ok(parse_diff(&execute_shell_checked(&script, state).await?))
+}
+
+/// A finished swarm with snapshots, and its project
+async fn reviewable(id: &str, state: &NexusState) -> Result<(SwarmRecord, String, String), String> {
+    let record = state.active_swarms.lock().await
+        .get(id).cloned()
+        .ok_or_else(|| format!("Swarm not found: {}", id))?;
+    if !record.status.is_finished() {
+        return Err("Swarm is still running".into());
+    }
+    let project = record.project.clone().ok_or("Swarm has no project")?;
+    let base = record.base_snapshot.clone().ok_or("No snapshot was taken for this swarm; is the project a git repository?")?;
+    Ok((record, project, base))
+}
+
+async fn build_diff(id: &str, app: &tauri::AppHandle, state: &NexusState) -> Result<SwarmDiff, String> {
+    let (mut record, project, base) = reviewable(id, state).await?;
+    let current = snapshot(&project, id, "review", state).await?;
+    let mut files = diff_between(&project, &base, &current, state).await?;
+
+    // Swarms from before final snapshots were kept are pinned at their
+    // first review, before anything could be rejected
+    let last = match &record.final_snapshot {
+        Some(last) => last.clone(),
+        None => {
+            if let Some(updated) = update_swarm(state, id, |r| r.final_snapshot = Some(current.clone())).await {
+                persist(app, &updated);
+                record = updated;
+            }
+            current.clone()
+        }
+    };
+    let reference = if last == current {
+        files.clone()
+    } else {
+        diff_between(&project, &base, &last, state).await?
+    };
+    apply_decisions(&mut files, &reference, &record);
+
+    let mut subtasks = Vec::new();
+    for subtask in &record.subtasks {
+        if let (Some(from), Some(to)) = (&subtask.diff_from, &subtask.diff_to) {
+            let mut files = diff_between(&project, from, to, state).await?;
+            apply_decisions(&mut files, &reference, &record);
+            subtasks.push(SubtaskDiff { subtask_id: subtask.id.clone(), files });
+        }
+    }
+    Ok(SwarmDiff { task_id: record.id, base, files, subtasks })
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Unified diffs of everything a finished swarm changed, overall and per subtask
+#[tauri::command]
+pub async fn get_swarm_diff(id: String, app: tauri::AppHandle, state: State<'_, NexusState>) -> Result<SwarmDiff, String> {
+    build_diff(&id, &app, &state).await
+}
+
+/// Accept or reject a changed file, or one hunk of it when `hunk` is set.
+/// Rejecting restores the file (or reverses the hunk) in the project;
+/// accepting keeps the change and records the decision.
+#[tauri::command]
+pub async fn review_swarm_change(
+    id: String,
+    path: String,
+    hunk: Option<usize>,
+    accept: bool,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<SwarmDiff, String> {
+    let diff = build_diff(&id, &app, &state).await?;
+    let (_, project, base) = reviewable(&id, &state).await?;
+    let file = diff.files.iter()
+        .find(|f| f.path == path)
+        .ok_or_else(|| format!("No pending change to {}", path))?;
+    let target = match hunk {
+        Some(index) => Some(file.hunks.get(index).ok_or_else(|| format!("No hunk {} in {}", index, path))?),
+        None => None,
+    };
+    let key = match target {
+        Some(hunk) => review_key(&path, Some(hunk.origin.ok_or("That hunk is not one of the swarm's changes")?)),
+        None => review_key(&path, None),
+    };
+
+    if !accept {
+        let script = match target {
+            Some(hunk) => format!(
+                "{} && printf '%s' {} | git apply -R --whitespace=nowarn -",
+                cd_toplevel(&project),
+                shell_quote(&format!("{}{}", file.header, hunk.patch)),
+            ),
+            None if file.status == "added" => format!("{} && rm -f -- {}", cd_toplevel(&project), shell_quote(&path)),
+            None => format!(
+                "{cd} && git checkout {base} -- {path} && git reset -q -- {path}",
+                cd = cd_toplevel(&project),
+                base = base,
+                path = shell_quote(&path),
+            ),
+        };
+        execute_shell_checked(&script, &state).await?;
+    }
+
+    let decision = if accept { ReviewDecision::Accepted } else { ReviewDecision::Rejected };
+    if let Some(record) = update_swarm(&state, &id, |r| {
+        r.review.insert(key, decision);
+    }).await {
+        persist(&app, &record);
+    }
+    build_diff(&id, &app, &state).await
+}
+
+/// Put the project back as it was before the swarm started. The current
+/// state, swarm changes included, is stashed first so it can be recovered
+/// with `git stash pop`.
+#[tauri::command]
+pub async fn rollback_swarm(id: String, app: tauri::AppHandle, state: State<'_, NexusState>) -> Result<(), String> {
+    let (_, project, base) = reviewable(&id, &state).await?;
+    let script = format!(
+        "{cd} && git stash push -u -q -m {message} && git checkout {base} -- . && git reset -q",
+        cd = cd_toplevel(&project),
+        message = shell_quote(&format!("nexus: before rolling back swarm {}", id)),
+        base = base,
+    );
+    execute_shell_checked(&script, &state).await?;
+
+    if let Some(record) = update_swarm(&state, &id, |r| {
+        r.review.clear();
+        r.rolled_back = true;
+    }).await {
+        persist(&app, &record);
+        emit_progress(&app, &record);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SWARM_DIFF: &str = "diff --git a/x b/y.txt b/x b/y.txt
+index 1111111..2222222 100644
+--- a/x b/y.txt
++++ b/x b/y.txt
+@@ -1,3 +1,3 @@
+-one
++ONE
+ two
+ three
+@@ -20,3 +20,4 @@
+ twenty
++inserted
+ twenty-one
+ twenty-two
+diff --git \"a/\\303\\251.rs\" \"b/\\303\\251.rs\"
+new file mode 100644
+index 0000000..3333333
+--- /dev/null
++++ \"b/\\303\\251.rs\"
+@@ -0,0 +1 @@
++fn main() {}
+";
+
+    #[test]
+    fn paths_with_spaces_and_quotes() {
+        let files = parse_diff(SWARM_DIFF);
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["x b/y.txt", "é.rs"]);
+        assert_eq!(files[0].hunks.len(), 2);
+        assert_eq!(files[1].status, "added");
+    }
+
+    #[test]
+    fn decisions_survive_rejecting_an_earlier_hunk() {
+        let reference = parse_diff(SWARM_DIFF);
+        let mut record = SwarmRecord::new(String::new(), None, None);
+        record.review.insert(review_key("x b/y.txt", Some(0)), ReviewDecision::Rejected);
+        record.review.insert(review_key("x b/y.txt", Some(1)), ReviewDecision::Accepted);
+
+        // The project after reversing the first hunk: the second is all
+        // that's left, at its original place
+        let now = "diff --git a/x b/y.txt b/x b/y.txt
+--- a/x b/y.txt
++++ b/x b/y.txt
+@@ -20,3 +20,4 @@
+ twenty
++inserted
+ twenty-one
+";
+        let mut files = parse_diff(now);
+        apply_decisions(&mut files, &reference, &record);
+        let hunk = &files[0].hunks[0];
+        assert_eq!((hunk.index, hunk.origin, hunk.decision), (0, Some(1), ReviewDecision::Accepted));
+
+        // A change the swarm didn't make has nothing to key a decision on
+        let mut files = parse_diff("diff --git a/x b/y.txt b/x b/y.txt\n@@ -5 +5 @@\n-five\n+5\n");
+        apply_decisions(&mut files, &reference, &record);
+        assert_eq!((files[0].hunks[0].origin, files[0].hunks[0].decision), (None, ReviewDecision::Pending));
+    }
+}
@@ -0,0 +1,207 @@
+// Swarm worktrees - run each subtask in its own git worktree on the target
+// host and merge its changes back into the project
+
+use super::review::{cd_toplevel, changed_files, snapshot, SNAPSHOT_IDENTITY};
+use super::{MergeConflict, SwarmRecord};
+use crate::{execute_shell_checked, shell_quote, NexusState};
+
+/// A worktree checked out for one subtask
+#[derive(Debug, Clone)]
+pub struct Worktree {
+    pub root: String,                   // Worktree directory
+    pub dir: String,                    // Where the worker runs: the project's path inside `root`
+    pub branch: String,
+    pub start: String,                  // Snapshot the worktree was created from
+}
+
+/// Characters allowed in branch and directory names
+fn slug(value: &str) -> String {
+    value.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect()
+}
+
+/// Check out `start` into a new worktree under the repository's git
+/// directory. Files ignored by git are not in snapshots, so workers start
+/// without build output or installed dependencies.
+pub async fn create(project: &str, id: &str, subtask: &str, start: &str, state: &NexusState) -> Result<Worktree, String> {
+    let short_id: String = id.chars().take(8).collect();
+    let branch = format!("nexus/swarm-{}/{}", short_id, slug(subtask));
+    let script = format!(
+        "cd {project} && prefix=$(git rev-parse --show-prefix) && cd \"$(git rev-parse --show-toplevel)\" \
+         && root=\"$(cd \"$(git rev-parse --git-common-dir)\" && pwd)/nexus-worktrees/{id}/{subtask}\" \
+         && git worktree add -q -f -B {branch} \"$root\" {start} >/dev/null \
+         && printf '%s\\n%s\\n' \"$root\" \"$prefix\"",
+        project = shell_quote(project),
+        id = slug(id),
+        subtask = slug(subtask),
+        branch = shell_quote(&branch),
+        start = start,
+    );
+    let output = execute_shell_checked(&script, state).await?;
+    let mut lines = output.lines();
+    let root = lines.next().filter(|l| !l.is_empty()).ok_or("git did not report the worktree path")?.to_string();
+    let prefix = lines.next().unwrap_or("").trim_end_matches('/');
+    let dir = if prefix.is_empty() { root.clone() } else { format!("{}/{}", root, prefix) };
+    Ok(Worktree { root, dir, branch, start: start.to_string() })
+}
+
+/// Commit everything the worker left in the worktree, returning the commit
+pub async fn commit(worktree: &Worktree, message: &str, state: &NexusState) -> Result<String, String> {
+    let script = format!(
+        "cd {root} && git add -A && {identity} git commit -q --allow-empty --no-verify -m {message} && git rev-parse HEAD",
+        root = shell_quote(&worktree.root),
+        identity = SNAPSHOT_IDENTITY,
+        message = shell_quote(message),
+    );
+    Ok(execute_shell_checked(&script, state).await?.trim().to_string())
+}
+
+/// Remove the worktree directory, and its branch unless `keep_branch`
+pub async fn remove(project: &str, worktree: &Worktree, keep_branch: bool, state: &NexusState) -> Result<(), String> {
+    let mut script = format!("{} && git worktree remove --force {}", cd_toplevel(project), shell_quote(&worktree.root));
+    if !keep_branch {
+        script.push_str(&format!(" && git branch -q -D {}", shell_quote(&worktree.branch)));
+    }
+    execute_shell_checked(&script, state).await.map(|_| ())
+}
+
+/// Bring a finished worktree's changes (`start..head`) into the project.
+///
+/// Files nobody else touched since `start` are copied over. Files that also
+/// changed in the project are conflicts: with `auto_merge` they go through a
+/// three-way `git merge-file` (leaving conflict markers when that fails),
+/// otherwise the project's version is kept and the change stays on the
+/// worktree branch. Returns the files the subtask changed and the conflicts.
+pub async fn merge_back(
+    project: &str,
+    worktree: &Worktree,
+    head: &str,
+    subtask: &str,
+    auto_merge: bool,
+    record: &SwarmRecord,
+    state: &NexusState,
+) -> Result<(Vec<String>, Vec<MergeConflict>), String> {
+    let script = format!("{} && git diff --no-renames --name-status {} {}", cd_toplevel(project), worktree.start, head);
+    let changes: Vec<(String, String)> = execute_shell_checked(&script, state).await?
+        .lines()
+        .filter_map(|l| l.split_once('\t'))
+        .map(|(status, path)| (status.to_string(), path.to_string()))
+        .collect();
+    if changes.is_empty() {
+        return Ok((Vec::new(), Vec::new()));
+    }
+
+    let current = snapshot(project, &record.id, &format!("merge-{}", subtask), state).await?;
+    let touched = changed_files(project, &worktree.start, &current, state).await?;
+
+    let mut copy = Vec::new();
+    let mut delete = Vec::new();
+    let mut conflicts = Vec::new();
+    for (status, path) in &changes {
+        if !touched.contains(path) {
+            if status == "D" { delete.push(path) } else { copy.push(path) }
+            continue;
+        }
+
+        // Whoever changed the file first: an earlier subtask, or the user
+        let other = record.subtasks.iter()
+            .find(|s| s.id != subtask && s.files_modified.contains(path))
+            .map_or_else(|| "workspace".to_string(), |s| s.id.clone());
+        let resolution = if auto_merge && status == "M" {
+            merge_file(project, path, &worktree.start, head, subtask, state).await
+        } else {
+            "manual_required"
+        };
+        conflicts.push(MergeConflict {
+            file_path: path.clone(),
+            worker_a: other,
+            worker_b: subtask.to_string(),
+            resolution: resolution.to_string(),
+        });
+    }
+
+    let quoted = |paths: &[&String]| paths.iter().map(|p| shell_quote(p)).collect::<Vec<_>>().join(" ");
+    let mut steps = Vec::new();
+    if !copy.is_empty() {
+        steps.push(format!("git checkout {head} -- {paths} && git reset -q -- {paths}", head = head, paths = quoted(&copy)));
+    }
+    if !delete.is_empty() {
+        steps.push(format!("rm -f -- {}", quoted(&delete)));
+    }
+    if !steps.is_empty() {
+        execute_shell_checked(&format!("{} && {}", cd_toplevel(project), steps.join(" && ")), state).await?;
+    }
+
+    Ok((changes.into_iter().map(|(_, path)| path).collect(), conflicts))
+}
+
+/// Three-way merge of one file in place: "auto_merged" when clean,
+/// "manual_required" when conflict markers were left in the file
+async fn merge_file(project: &str, path: &str, start: &str, head: &str, subtask: &str, state: &NexusState) -> &'static str {
+    match execute_shell_checked(&merge_file_script(project, path, start, head, subtask), state).await {
+        Ok(_) => "auto_merged",
+        Err(_) => "manual_required",
+    }
+}
+
+/// The script behind `merge_file`. It must not `exit`, so that
+/// `execute_shell_checked` can still report its status.
+fn merge_file_script(project: &str, path: &str, start: &str, head: &str, subtask: &str) -> String {
+    format!(
+        "{cd} && base=$(mktemp) && theirs=$(mktemp) \
+         && git show {start}:{path} > \"$base\" && git show {head}:{path} > \"$theirs\" \
+         && {{ git merge-file -L project -L base -L {label} {path} \"$base\" \"$theirs\"; rc=$?; rm -f \"$base\" \"$theirs\"; (exit $rc); }}",
+        cd = cd_toplevel(project),
+        start = start,
+        head = head,
+        path = shell_quote(path),
+        label = shell_quote(subtask),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_exit_status, report_exit_status};
+    use std::process::Command;
+
+    fn sh(dir: &std::path::Path, script: &str) -> String {
+        let output = Command::new("sh").arg("-c").arg(script).current_dir(dir).output().unwrap();
+        // Stdout then stderr, as execute_shell_bridge returns them
+        format!("{}\n{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr))
+    }
+
+    fn merge_in_scratch_repo(project_edit: &str) -> (Result<String, String>, String) {
+        let dir = std::env::temp_dir().join(format!("nexus-merge-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let setup = format!(
+            "git init -q && {id} && printf 'one\\ntwo\\nthree\\nfour\\nfive\\n' > f.txt \
+             && git add f.txt && git commit -q -m base \
+             && sed -i 's/five/FIVE/' f.txt && git commit -q -am worker \
+             && git reset -q --hard HEAD~1 && {edit}",
+            id = "git config user.name t && git config user.email t@t",
+            edit = project_edit,
+        );
+        sh(&dir, &setup);
+        let script = merge_file_script(&dir.to_string_lossy(), "f.txt", "HEAD", "HEAD@{1}", "s1");
+        let result = parse_exit_status(&sh(&dir, &report_exit_status(&script)));
+        let merged = std::fs::read_to_string(dir.join("f.txt")).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        (result, merged)
+    }
+
+    #[test]
+    fn clean_three_way_merge_succeeds() {
+        let (result, merged) = merge_in_scratch_repo("sed -i 's/one/ONE/' f.txt");
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(merged, "ONE\ntwo\nthree\nfour\nFIVE\n");
+    }
+
+    #[test]
+    fn overlapping_edits_fail_with_markers() {
+        let (result, merged) = merge_in_scratch_repo("sed -i 's/five/5/' f.txt");
+        assert!(result.is_err());
+        assert!(merged.contains("<<<<<<< project"));
+    }
+}
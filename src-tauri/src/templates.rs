@@ -0,0 +1,179 @@
+// Prompt templates - reusable prompts with {{variables}} and slash-command expansion
+
+use serde::{Deserialize, Serialize};
+use ssh2::Session;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    pub name: String,
+    pub description: Option<String>,
+    pub body: String,
+    pub variables: Vec<String>,
+    pub source: String,                 // "builtin", "user" or "project"
+}
+
+/// Templates available without any configuration
+pub fn builtin() -> Vec<PromptTemplate> {
+    [
+        ("review", "Review a diff or change",
+         "Review this change for bugs, missing edge cases and style issues. Be specific and cite lines.\n\n{{input}}"),
+        ("tests", "Write tests for a file or function",
+         "Write thorough tests for {{input}}. Follow the project's existing test layout and conventions."),
+        ("explain", "Explain code",
+         "Explain what {{input}} does, how it fits into the project, and anything surprising about it."),
+    ]
+    .into_iter()
+    .map(|(name, description, body)| PromptTemplate {
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        body: body.to_string(),
+        variables: variables(body),
+        source: "builtin".to_string(),
+    })
+    .collect()
+}
+
+/// Variable names in order of first appearance, e.g. `{{ path }}` -> "path"
+pub fn variables(body: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = body;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else { break };
+        let name = after[..end].trim().to_string();
+        if !name.is_empty() && !names.contains(&name) {
+            names.push(name);
+        }
+        rest = &after[end + 2..];
+    }
+    names
+}
+
+/// Parse a template file, reading an optional `description:` from a
+/// leading `---` front-matter block
+pub fn parse(name: &str, raw: &str, source: &str) -> PromptTemplate {
+    let mut description = None;
+    let mut body = raw;
+    if let Some(front) = raw.strip_prefix("---\n") {
+        if let Some(end) = front.find("\n---") {
+            for line in front[..end].lines() {
+                if let Some(value) = line.strip_prefix("description:") {
+                    description = Some(value.trim().to_string());
+                }
+            }
+            body = front[end + 4..].trim_start_matches('\n');
+        }
+    }
+    PromptTemplate {
+        name: name.to_string(),
+        description,
+        body: body.trim_end().to_string(),
+        variables: variables(body),
+        source: source.to_string(),
+    }
+}
+
+fn template_name(path: &Path) -> Option<String> {
+    if path.extension()? != "md" {
+        return None;
+    }
+    Some(path.file_stem()?.to_string_lossy().to_string())
+}
+
+/// Load `*.md` templates from a directory on the desktop
+pub fn load_local(dir: &Path, source: &str) -> Vec<PromptTemplate> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|e| {
+            let path = e.path();
+            let name = template_name(&path)?;
+            let raw = std::fs::read_to_string(&path).ok()?;
+            Some(parse(&name, &raw, source))
+        })
+        .collect()
+}
+
+/// Load `*.md` templates from a directory on the SSH host
+pub fn load_sftp(sess: &Session, dir: &Path, source: &str) -> Vec<PromptTemplate> {
+    let Ok(sftp) = sess.sftp() else {
+        return Vec::new();
+    };
+    let Ok(entries) = sftp.readdir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .into_iter()
+        .filter(|(_, stat)| stat.is_file())
+        .filter_map(|(path, _)| {
+            let name = template_name(&path)?;
+            let mut raw = String::new();
+            sftp.open(&path).ok()?.read_to_string(&mut raw).ok()?;
+            Some(parse(&name, &raw, source))
+        })
+        .collect()
+}
+
+/// Combine template sets; later sets override earlier ones by name
+pub fn merge(sets: Vec<Vec<PromptTemplate>>) -> Vec<PromptTemplate> {
+    let mut merged: Vec<PromptTemplate> = Vec::new();
+    for template in sets.into_iter().flatten() {
+        merged.retain(|t| t.name != template.name);
+        merged.push(template);
+    }
+    merged.sort_by(|a, b| a.name.cmp(&b.name));
+    merged
+}
+
+pub fn render(template: &PromptTemplate, vars: &HashMap<String, String>) -> Result<String, String> {
+    let mut output = String::new();
+    let mut rest = template.body.as_str();
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else { break };
+        let name = after[..end].trim();
+        let value = vars
+            .get(name)
+            .ok_or_else(|| format!("Missing value for '{}' in template '{}'", name, template.name))?;
+        output.push_str(&rest[..start]);
+        output.push_str(value);
+        rest = &after[end + 2..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Expand `/name key=value free text` into the named template. `key=value`
+/// pairs fill variables by name and any remaining text fills the first
+/// unfilled variable. Messages that are not a known slash command are
+/// returned unchanged.
+pub fn expand_slash_command(message: &str, templates: &[PromptTemplate]) -> Result<String, String> {
+    let Some(command) = message.strip_prefix('/') else {
+        return Ok(message.to_string());
+    };
+    let (name, args) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+    let Some(template) = templates.iter().find(|t| t.name == name) else {
+        return Ok(message.to_string());
+    };
+
+    let mut vars = HashMap::new();
+    let mut free_text = Vec::new();
+    for token in args.split(' ') {
+        match token.split_once('=') {
+            Some((key, value)) if template.variables.iter().any(|v| v == key) => {
+                vars.insert(key.to_string(), value.to_string());
+            }
+            _ => free_text.push(token),
+        }
+    }
+    let free_text = free_text.join(" ").trim().to_string();
+    if let Some(first_unfilled) = template.variables.iter().find(|v| !vars.contains_key(*v)) {
+        vars.insert(first_unfilled.clone(), free_text);
+    }
+    render(template, &vars)
+}
@@ -0,0 +1,506 @@
+// File transfer - copy files and directories between this machine and the
+// SSH host over SFTP, with progress events and resumable partial files
+
+use filetime::FileTime;
+use serde::Serialize;
+use ssh2::{FileStat, OpenFlags, OpenType, Session, Sftp};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{Emitter, State};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::files::replace_sftp;
+use crate::projects::check_allowed;
+use crate::{establish_ssh, NexusState};
+
+const CHUNK_BYTES: usize = 256 * 1024;
+
+/// Minimum gap between `nexus://transfer-progress` events
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Suffix of a file still being transferred; a later run resumes from it
+const PART_SUFFIX: &str = ".nexus-part";
+
+/// Suffix of the note beside a partial file naming the source it came
+/// from, so a source that changed since is copied afresh
+const STAMP_SUFFIX: &str = ".nexus-part.src";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Upload,
+    Download,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferSummary {
+    pub id: String,
+    pub direction: Direction,
+    pub source: String,
+    pub destination: String,
+    pub files: usize,                   // Transferred this run
+    pub bytes: u64,
+    pub skipped: usize,                 // Already complete at the destination
+    pub resumed: usize,                 // Continued from a partial file
+}
+
+/// One file to copy
+struct Item {
+    src: PathBuf,
+    dst: PathBuf,
+    size: u64,
+    mtime: i64,                         // Unix seconds
+    mode: u32,
+}
+
+/// Everything a transfer will create, found before any bytes move so
+/// progress has totals
+#[derive(Default)]
+struct Plan {
+    dirs: Vec<(PathBuf, u32)>,          // Destination and mode, parents first
+    files: Vec<Item>,
+}
+
+struct Progress {
+    app: tauri::AppHandle,
+    id: String,
+    direction: Direction,
+    files_total: usize,
+    files_done: usize,
+    bytes_total: u64,
+    bytes_done: u64,
+    file: String,
+    last_emit: Instant,
+}
+
+impl Progress {
+    fn advance(&mut self, bytes: u64) {
+        self.bytes_done += bytes;
+        if self.last_emit.elapsed() >= PROGRESS_INTERVAL {
+            self.emit();
+        }
+    }
+
+    fn emit(&mut self) {
+        self.last_emit = Instant::now();
+        let _ = self.app.emit("nexus://transfer-progress", serde_json::json!({
+            "id": self.id,
+            "direction": self.direction,
+            "file": self.file,
+            "filesDone": self.files_done,
+            "filesTotal": self.files_total,
+            "bytesDone": self.bytes_done,
+            "bytesTotal": self.bytes_total,
+        }));
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Identifies the version of the source a partial file holds
+fn stamp(item: &Item) -> String {
+    format!("{} {}", item.size, item.mtime)
+}
+
+fn file_name(path: &Path) -> Result<&std::ffi::OsStr, String> {
+    path.file_name().ok_or_else(|| format!("{} has no file name", path.display()))
+}
+
+/// A destination with the source's size and mtime was written by an
+/// earlier run, which copies the mtime over
+fn is_complete(size: Option<u64>, mtime: Option<i64>, item: &Item) -> bool {
+    size == Some(item.size) && mtime == Some(item.mtime)
+}
+
+fn copy(src: &mut impl Read, dst: &mut impl Write, progress: &mut Progress) -> Result<(), String> {
+    let mut buf = vec![0; CHUNK_BYTES];
+    loop {
+        let n = src.read(&mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Ok(());
+        }
+        dst.write_all(&buf[..n]).map_err(|e| e.to_string())?;
+        progress.advance(n as u64);
+    }
+}
+
+#[cfg(unix)]
+fn local_mode(meta: &std::fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+fn local_mode(meta: &std::fs::Metadata) -> u32 {
+    if meta.is_dir() { 0o755 } else { 0o644 }
+}
+
+#[cfg(unix)]
+fn set_local_mode(path: &Path, mode: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn set_local_mode(_path: &Path, _mode: u32) -> std::io::Result<()> {
+    Ok(())
+}
+
+fn mode_stat(mode: u32) -> FileStat {
+    FileStat { size: None, uid: None, gid: None, perm: Some(mode), atime: None, mtime: None }
+}
+
+// ============================================================================
+// Upload
+// ============================================================================
+
+/// Walk local `src`. Symlinked directories below the top are not followed
+/// and unreadable entries are skipped.
+fn plan_local(src: &Path, dst: &Path, top: bool, plan: &mut Plan) -> Result<(), String> {
+    let meta = match std::fs::metadata(src) {
+        Ok(meta) => meta,
+        Err(e) if top => return Err(format!("{}: {}", src.display(), e)),
+        Err(_) => return Ok(()),
+    };
+    if !meta.is_dir() {
+        plan.files.push(Item {
+            src: src.to_path_buf(),
+            dst: dst.to_path_buf(),
+            size: meta.len(),
+            mtime: meta.modified().ok()
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs() as i64),
+            mode: local_mode(&meta),
+        });
+        return Ok(());
+    }
+    if !top && std::fs::symlink_metadata(src).is_ok_and(|m| m.file_type().is_symlink()) {
+        return Ok(());
+    }
+
+    plan.dirs.push((dst.to_path_buf(), local_mode(&meta)));
+    let entries = std::fs::read_dir(src).map_err(|e| format!("{}: {}", src.display(), e))?;
+    for entry in entries.flatten() {
+        plan_local(&entry.path(), &dst.join(entry.file_name()), false, plan)?;
+    }
+    Ok(())
+}
+
+fn upload_file(sess: &Session, sftp: &Sftp, item: &Item, progress: &mut Progress) -> Result<Option<bool>, String> {
+    if let Ok(stat) = sftp.stat(&item.dst) {
+        if is_complete(stat.size, stat.mtime.map(|m| m as i64), item) {
+            progress.advance(item.size);
+            return Ok(None);
+        }
+    }
+
+    let part = with_suffix(&item.dst, PART_SUFFIX);
+    let stamp_file = with_suffix(&item.dst, STAMP_SUFFIX);
+    let same_source = sftp.open(&stamp_file).ok()
+        .and_then(|mut f| {
+            let mut text = String::new();
+            f.read_to_string(&mut text).ok().map(|_| text)
+        })
+        .is_some_and(|text| text == stamp(item));
+    let offset = if same_source {
+        sftp.stat(&part).ok().and_then(|s| s.size).filter(|s| *s <= item.size).unwrap_or(0)
+    } else {
+        0
+    };
+    if offset == 0 {
+        let mut note = sftp.create(&stamp_file).map_err(|e| format!("{}: {}", stamp_file.display(), e))?;
+        note.write_all(stamp(item).as_bytes()).map_err(|e| e.to_string())?;
+    }
+    let mut src = std::fs::File::open(&item.src).map_err(|e| format!("{}: {}", item.src.display(), e))?;
+    src.seek(SeekFrom::Start(offset)).map_err(|e| e.to_string())?;
+    let mut flags = OpenFlags::WRITE | OpenFlags::CREATE;
+    if offset == 0 {
+        flags |= OpenFlags::TRUNCATE;
+    }
+    let mut dst = sftp.open_mode(&part, flags, 0o600, OpenType::File)
+        .map_err(|e| format!("{}: {}", part.display(), e))?;
+    dst.seek(SeekFrom::Start(offset)).map_err(|e| e.to_string())?;
+    progress.advance(offset);
+    copy(&mut src, &mut dst, progress)?;
+    drop(dst);
+
+    // mtime is kept so the next run can tell the copy is complete
+    sftp.setstat(&part, FileStat {
+        mtime: Some(item.mtime as u64),
+        atime: Some(item.mtime as u64),
+        ..mode_stat(item.mode)
+    }).map_err(|e| e.to_string())?;
+    replace_sftp(sess, &part, &item.dst).map_err(|e| format!("{}: {}", item.dst.display(), e))?;
+    let _ = sftp.unlink(&stamp_file);
+    Ok(Some(offset > 0))
+}
+
+fn run_upload(sess: &Session, sftp: &Sftp, local: &Path, remote: &Path, progress: &mut Progress) -> Result<TransferSummary, String> {
+    let dst = match sftp.stat(remote) {
+        Ok(stat) if stat.is_dir() => remote.join(file_name(local)?),
+        _ => remote.to_path_buf(),
+    };
+    let mut plan = Plan::default();
+    plan_local(local, &dst, true, &mut plan)?;
+    let mut summary = start(progress, &plan, Direction::Upload, local, &dst);
+
+    for (dir, _) in &plan.dirs {
+        if sftp.stat(dir).is_err() {
+            sftp.mkdir(dir, 0o755).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+    }
+    for item in &plan.files {
+        progress.file = item.dst.to_string_lossy().to_string();
+        let outcome = upload_file(sess, sftp, item, progress)?;
+        finish_file(progress, &mut summary, item, outcome);
+    }
+    // Directory modes last, so read-only ones don't block their contents
+    for (dir, mode) in plan.dirs.iter().rev() {
+        let _ = sftp.setstat(dir, mode_stat(*mode));
+    }
+    Ok(summary)
+}
+
+// ============================================================================
+// Download
+// ============================================================================
+
+/// Walk remote `src`, mirroring `plan_local`
+fn plan_remote(sftp: &Sftp, src: &Path, dst: &Path, top: bool, plan: &mut Plan) -> Result<(), String> {
+    let stat = match sftp.stat(src) {
+        Ok(stat) => stat,
+        Err(e) if top => return Err(format!("{}: {}", src.display(), e)),
+        Err(_) => return Ok(()),
+    };
+    let mode = stat.perm.map_or(0o644, |p| p & 0o7777);
+    if !stat.is_dir() {
+        plan.files.push(Item {
+            src: src.to_path_buf(),
+            dst: dst.to_path_buf(),
+            size: stat.size.unwrap_or(0),
+            mtime: stat.mtime.map_or(0, |m| m as i64),
+            mode,
+        });
+        return Ok(());
+    }
+    if !top && sftp.lstat(src).is_ok_and(|s| s.file_type().is_symlink()) {
+        return Ok(());
+    }
+
+    plan.dirs.push((dst.to_path_buf(), mode));
+    let entries = sftp.readdir(src).map_err(|e| format!("{}: {}", src.display(), e))?;
+    for (path, _) in entries {
+        let Some(name) = path.file_name() else { continue };
+        plan_remote(sftp, &path, &dst.join(name), false, plan)?;
+    }
+    Ok(())
+}
+
+fn download_file(sftp: &Sftp, item: &Item, progress: &mut Progress) -> Result<Option<bool>, String> {
+    if let Ok(meta) = std::fs::metadata(&item.dst) {
+        let mtime = meta.modified().ok()
+            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64);
+        if is_complete(Some(meta.len()), mtime, item) {
+            progress.advance(item.size);
+            return Ok(None);
+        }
+    }
+
+    let part = with_suffix(&item.dst, PART_SUFFIX);
+    let stamp_file = with_suffix(&item.dst, STAMP_SUFFIX);
+    let same_source = std::fs::read_to_string(&stamp_file).is_ok_and(|text| text == stamp(item));
+    let offset = if same_source {
+        std::fs::metadata(&part).ok().map(|m| m.len()).filter(|s| *s <= item.size).unwrap_or(0)
+    } else {
+        0
+    };
+    if offset == 0 {
+        std::fs::write(&stamp_file, stamp(item)).map_err(|e| format!("{}: {}", stamp_file.display(), e))?;
+    }
+    let mut src = sftp.open(&item.src).map_err(|e| format!("{}: {}", item.src.display(), e))?;
+    src.seek(SeekFrom::Start(offset)).map_err(|e| e.to_string())?;
+    let mut dst = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(offset == 0)
+        .open(&part)
+        .map_err(|e| format!("{}: {}", part.display(), e))?;
+    dst.seek(SeekFrom::Start(offset)).map_err(|e| e.to_string())?;
+    progress.advance(offset);
+    copy(&mut src, &mut dst, progress)?;
+    drop(dst);
+
+    set_local_mode(&part, item.mode).map_err(|e| e.to_string())?;
+    // mtime is kept so the next run can tell the copy is complete
+    filetime::set_file_mtime(&part, FileTime::from_unix_time(item.mtime, 0)).map_err(|e| e.to_string())?;
+    std::fs::rename(&part, &item.dst).map_err(|e| format!("{}: {}", item.dst.display(), e))?;
+    let _ = std::fs::remove_file(&stamp_file);
+    Ok(Some(offset > 0))
+}
+
+fn run_download(sftp: &Sftp, remote: &Path, local: &Path, progress: &mut Progress) -> Result<TransferSummary, String> {
+    let dst = if local.is_dir() { local.join(file_name(remote)?) } else { local.to_path_buf() };
+    let mut plan = Plan::default();
+    plan_remote(sftp, remote, &dst, true, &mut plan)?;
+    let mut summary = start(progress, &plan, Direction::Download, remote, &dst);
+
+    for (dir, _) in &plan.dirs {
+        std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    for item in &plan.files {
+        progress.file = item.dst.to_string_lossy().to_string();
+        let outcome = download_file(sftp, item, progress)?;
+        finish_file(progress, &mut summary, item, outcome);
+    }
+    for (dir, mode) in plan.dirs.iter().rev() {
+        let _ = set_local_mode(dir, *mode);
+    }
+    Ok(summary)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+fn start(progress: &mut Progress, plan: &Plan, direction: Direction, src: &Path, dst: &Path) -> TransferSummary {
+    progress.files_total = plan.files.len();
+    progress.bytes_total = plan.files.iter().map(|f| f.size).sum();
+    progress.emit();
+    TransferSummary {
+        id: progress.id.clone(),
+        direction,
+        source: src.to_string_lossy().to_string(),
+        destination: dst.to_string_lossy().to_string(),
+        files: 0,
+        bytes: 0,
+        skipped: 0,
+        resumed: 0,
+    }
+}
+
+/// `outcome` is None for a skipped file, otherwise whether it was resumed
+fn finish_file(progress: &mut Progress, summary: &mut TransferSummary, item: &Item, outcome: Option<bool>) {
+    progress.files_done += 1;
+    match outcome {
+        None => summary.skipped += 1,
+        Some(resumed) => {
+            summary.files += 1;
+            summary.bytes += item.size;
+            if resumed {
+                summary.resumed += 1;
+            }
+        }
+    }
+    progress.emit();
+}
+
+/// Run a transfer on its own SSH session, so large copies don't hold the
+/// shared one, and report the outcome with `nexus://transfer-complete`
+async fn transfer(
+    app: tauri::AppHandle,
+    state: &NexusState,
+    direction: Direction,
+    src: PathBuf,
+    dst: PathBuf,
+) -> Result<TransferSummary, String> {
+    let creds = state.ssh_credentials.lock().await.clone()
+        .ok_or("Not connected to a remote host")?;
+    let remote = match direction {
+        Direction::Upload => &dst,
+        Direction::Download => &src,
+    };
+    check_allowed(remote, state).await?;
+    let id = uuid::Uuid::new_v4().to_string();
+    let mut progress = Progress {
+        app: app.clone(),
+        id: id.clone(),
+        direction,
+        files_total: 0,
+        files_done: 0,
+        bytes_total: 0,
+        bytes_done: 0,
+        file: String::new(),
+        last_emit: Instant::now(),
+    };
+
+    let result = tokio::task::spawn_blocking(move || {
+        let sess = establish_ssh(&creds)?;
+        let sftp = sess.sftp().map_err(|e| format!("SFTP unavailable: {}", e))?;
+        match direction {
+            Direction::Upload => run_upload(&sess, &sftp, &src, &dst, &mut progress),
+            Direction::Download => run_download(&sftp, &src, &dst, &mut progress),
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    match &result {
+        Ok(summary) => {
+            eprintln!("[Tauri] Transfer {} finished: {} files, {} skipped", id, summary.files, summary.skipped);
+            let _ = app.emit("nexus://transfer-complete", serde_json::json!({ "id": id, "summary": summary }));
+        }
+        Err(e) => {
+            eprintln!("[Tauri] Transfer {} failed: {}", id, e);
+            let _ = app.emit("nexus://transfer-complete", serde_json::json!({ "id": id, "error": e }));
+        }
+    }
+    result
+}
+
+/// Ask for a local path with the native dialog; None if the user cancelled
+async fn pick_local(app: &tauri::AppHandle, title: &'static str, directory: bool) -> Result<Option<PathBuf>, String> {
+    let dialog = app.dialog().file().set_title(title);
+    let picked = tokio::task::spawn_blocking(move || {
+        if directory { dialog.blocking_pick_folder() } else { dialog.blocking_pick_file() }
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    picked.map(|p| p.into_path().map_err(|e| e.to_string())).transpose()
+}
+
+/// Copy a local file or directory to `remote` on the SSH host. Without
+/// `local` a file picker opens (a folder picker with `directory`); returns
+/// None if it is dismissed. Interrupted copies resume on the next run.
+#[tauri::command]
+pub async fn upload_path(
+    local: Option<String>,
+    remote: String,
+    directory: Option<bool>,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<Option<TransferSummary>, String> {
+    let local = match local {
+        Some(path) => PathBuf::from(path),
+        None => match pick_local(&app, "Choose what to upload", directory.unwrap_or(false)).await? {
+            Some(path) => path,
+            None => return Ok(None),
+        },
+    };
+    transfer(app, &state, Direction::Upload, local, PathBuf::from(remote)).await.map(Some)
+}
+
+/// Copy a remote file or directory to this machine. Without `local` a
+/// folder picker asks where to put it; returns None if it is dismissed.
+#[tauri::command]
+pub async fn download_path(
+    remote: String,
+    local: Option<String>,
+    app: tauri::AppHandle,
+    state: State<'_, NexusState>,
+) -> Result<Option<TransferSummary>, String> {
+    let local = match local {
+        Some(path) => PathBuf::from(path),
+        None => match pick_local(&app, "Choose where to download to", true).await? {
+            Some(path) => path,
+            None => return Ok(None),
+        },
+    };
+    transfer(app, &state, Direction::Download, PathBuf::from(remote), local).await.map(Some)
+}
@@ -0,0 +1,217 @@
+// Token usage and cost accounting for assistant messages
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tauri::Manager;
+
+/// Usage metadata captured for a single assistant message
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MessageUsage {
+    pub model: Option<String>,
+    pub provider: Option<String>,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub latency_ms: u64,
+    pub cost: f64,                      // USD
+    #[serde(default)]
+    pub estimated: bool,                // Token counts derived from text length
+}
+
+/// One entry in the persisted usage ledger
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageRecord {
+    pub timestamp: String,
+    pub source: String,                 // "chat", "swarm" or "commit_message"
+    pub conversation_id: Option<String>,
+    pub message_id: Option<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(flatten)]
+    pub usage: MessageUsage,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageBucket {
+    pub key: String,
+    pub messages: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+    pub avg_latency_ms: u64,
+}
+
+/// Rough token count for text when the CLI does not report usage
+pub fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64 + 3) / 4
+}
+
+/// Blended price per million tokens, from the model capability table
+pub fn price_per_1m_tokens(model: &str) -> Option<f64> {
+    let caps = crate::model_capabilities();
+    let exact = caps.iter().find(|c| c["id"].as_str() == Some(model));
+    // Provider-prefixed ids such as "anthropic/claude-sonnet-4-5"
+    let suffix = || {
+        caps.iter().find(|c| {
+            c["id"].as_str()
+                .is_some_and(|id| model.ends_with(&format!("/{}", id)))
+        })
+    };
+    exact.or_else(suffix).and_then(|c| c["cost_per_1m_tokens"].as_f64())
+}
+
+pub fn cost_for(model: Option<&str>, tokens: u64) -> f64 {
+    model
+        .and_then(price_per_1m_tokens)
+        .map(|price| price * tokens as f64 / 1_000_000.0)
+        .unwrap_or(0.0)
+}
+
+/// Read usage from a `nexus --json chat` response, estimating token counts
+/// from the prompt and reply when the CLI does not report them.
+pub fn from_response(raw: &str, prompt: &str, reply: &str, latency_ms: u64) -> MessageUsage {
+    let json = serde_json::from_str::<serde_json::Value>(raw).unwrap_or_default();
+    let data = &json["data"];
+    let reported = &data["usage"];
+
+    let prompt_tokens = reported["prompt_tokens"].as_u64()
+        .or_else(|| reported["input_tokens"].as_u64());
+    let completion_tokens = reported["completion_tokens"].as_u64()
+        .or_else(|| reported["output_tokens"].as_u64());
+
+    MessageUsage {
+        model: data["model"].as_str().map(|s| s.to_string()),
+        provider: data["provider"].as_str().map(|s| s.to_string()),
+        prompt_tokens: prompt_tokens.unwrap_or_else(|| estimate_tokens(prompt)),
+        completion_tokens: completion_tokens.unwrap_or_else(|| estimate_tokens(reply)),
+        latency_ms,
+        cost: 0.0,
+        estimated: prompt_tokens.is_none() || completion_tokens.is_none(),
+    }
+}
+
+/// Group ledger entries by "conversation", "day" or "provider"
+pub fn aggregate(records: &[UsageRecord], group_by: &str) -> Result<Vec<UsageBucket>, String> {
+    let mut buckets: BTreeMap<String, (UsageBucket, u64)> = BTreeMap::new();
+    for record in records {
+        let key = match group_by {
+            "conversation" => record.conversation_id.clone().unwrap_or_else(|| "none".into()),
+            "day" => record.timestamp.get(..10).unwrap_or("unknown").to_string(),
+            "provider" => record.usage.provider.clone().unwrap_or_else(|| "unknown".into()),
+            other => return Err(format!("Unknown grouping: {}", other)),
+        };
+        let (bucket, total_latency) = buckets.entry(key.clone()).or_insert_with(|| {
+            (UsageBucket { key, ..Default::default() }, 0)
+        });
+        bucket.messages += 1;
+        bucket.prompt_tokens += record.usage.prompt_tokens;
+        bucket.completion_tokens += record.usage.completion_tokens;
+        bucket.cost += record.usage.cost;
+        *total_latency += record.usage.latency_ms;
+    }
+    Ok(buckets
+        .into_values()
+        .map(|(mut bucket, total_latency)| {
+            bucket.avg_latency_ms = total_latency / bucket.messages.max(1);
+            bucket
+        })
+        .collect())
+}
+
+// ============================================================================
+// Persistence
+// ============================================================================
+
+fn ledger_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir.join("usage.json"))
+}
+
+pub fn load(app: &tauri::AppHandle) -> Vec<UsageRecord> {
+    ledger_path(app)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(app: &tauri::AppHandle, records: &[UsageRecord]) -> Result<(), String> {
+    let json = serde_json::to_string(records).map_err(|e| e.to_string())?;
+    std::fs::write(ledger_path(app)?, json).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(timestamp: &str, provider: Option<&str>, cost: f64, latency_ms: u64) -> UsageRecord {
+        UsageRecord {
+            timestamp: timestamp.to_string(),
+            source: "chat".to_string(),
+            conversation_id: None,
+            message_id: None,
+            project: None,
+            usage: MessageUsage {
+                provider: provider.map(|p| p.to_string()),
+                prompt_tokens: 10,
+                completion_tokens: 5,
+                latency_ms,
+                cost,
+                ..MessageUsage::default()
+            },
+        }
+    }
+
+    #[test]
+    fn tokens_are_estimated_per_four_characters() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+        // Characters, not bytes
+        assert_eq!(estimate_tokens("éééé"), 1);
+    }
+
+    #[test]
+    fn prices_match_exact_and_provider_prefixed_ids() {
+        assert_eq!(price_per_1m_tokens("claude-sonnet-4-5"), Some(3.0));
+        assert_eq!(price_per_1m_tokens("anthropic/claude-sonnet-4-5"), Some(3.0));
+        assert_eq!(price_per_1m_tokens("claude-sonnet-4-5-preview"), None);
+        assert_eq!(price_per_1m_tokens("xclaude-sonnet-4-5"), None);
+        assert_eq!(cost_for(Some("anthropic/claude-opus-4-6"), 2_000_000), 30.0);
+        assert_eq!(cost_for(Some("unknown-model"), 2_000_000), 0.0);
+        assert_eq!(cost_for(None, 2_000_000), 0.0);
+    }
+
+    #[test]
+    fn reported_usage_wins_over_estimates() {
+        let raw = r#"{"success":true,"data":{"model":"m","provider":"p","usage":{"input_tokens":7,"output_tokens":3}}}"#;
+        let usage = from_response(raw, "prompt", "reply", 12);
+        assert_eq!((usage.prompt_tokens, usage.completion_tokens, usage.estimated), (7, 3, false));
+        assert_eq!((usage.model.as_deref(), usage.provider.as_deref()), (Some("m"), Some("p")));
+
+        let usage = from_response("plain text", "12345678", "1234", 12);
+        assert_eq!((usage.prompt_tokens, usage.completion_tokens, usage.estimated), (2, 1, true));
+    }
+
+    #[test]
+    fn aggregates_by_day_and_provider() {
+        let records = [
+            record("2026-10-17T23:59:00Z", Some("openai"), 0.5, 100),
+            record("2026-10-18T00:01:00Z", Some("openai"), 0.25, 300),
+            record("2026-10-18T09:00:00Z", None, 1.0, 200),
+        ];
+
+        let days = aggregate(&records, "day").unwrap();
+        let summary: Vec<(&str, u64, u64, f64, u64)> = days.iter()
+            .map(|b| (b.key.as_str(), b.messages, b.prompt_tokens, b.cost, b.avg_latency_ms))
+            .collect();
+        assert_eq!(summary, [("2026-10-17", 1, 10, 0.5, 100), ("2026-10-18", 2, 20, 1.25, 250)]);
+
+        let providers = aggregate(&records, "provider").unwrap();
+        let summary: Vec<(&str, u64, f64)> = providers.iter().map(|b| (b.key.as_str(), b.messages, b.cost)).collect();
+        assert_eq!(summary, [("openai", 2, 0.75), ("unknown", 1, 1.0)]);
+
+        assert!(aggregate(&records, "week").is_err());
+    }
+}
@@ -0,0 +1,335 @@
+// Project file watcher - the notify crate for local projects and inotifywait
+// over SSH for remote ones, debounced and filtered through .gitignore
+
+use notify::event::{ModifyKind, RenameMode};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::gitignore::Gitignore;
+use crate::process::{self, ProcessEvent};
+use crate::{execute_shell_bridge, shell_quote, with_ssh_session, NexusState};
+
+/// Changes to the same path within this window are folded into one event
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeType {
+    Added,
+    Modified,
+    Deleted,
+}
+
+/// One change as reported by the backend, before debouncing
+struct RawChange {
+    path: String,                       // Absolute
+    change: ChangeType,
+    is_dir: bool,
+}
+
+enum Backend {
+    Local(RecommendedWatcher),          // Stops watching when dropped
+    Remote(Arc<std::sync::Mutex<Option<u32>>>),   // inotifywait PID once known
+}
+
+/// A running watcher, held in `NexusState::file_watcher`
+pub struct WatchHandle {
+    pub project: String,
+    pub mode: &'static str,             // "local" or "ssh"
+    alive: Arc<AtomicBool>,
+    backend: Backend,
+    task: tauri::async_runtime::JoinHandle<()>,
+}
+
+impl WatchHandle {
+    pub fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::Relaxed)
+    }
+}
+
+/// Fold a new change into the pending set for its path
+fn coalesce(pending: &mut BTreeMap<String, (ChangeType, bool)>, change: RawChange) {
+    use ChangeType::*;
+    let merged = match (pending.get(&change.path).map(|(c, _)| *c), change.change) {
+        (Some(Added), Deleted) => None,     // Created and removed again: nothing to report
+        (Some(Added), _) => Some(Added),
+        (Some(Deleted), Added) => Some(Modified),
+        (_, new) => Some(new),
+    };
+    match merged {
+        Some(kind) => {
+            pending.insert(change.path, (kind, change.is_dir));
+        }
+        None => {
+            pending.remove(&change.path);
+        }
+    }
+}
+
+async fn load_ignore(app: &tauri::AppHandle, project: &str, remote: bool) -> Gitignore {
+    if !remote {
+        return Gitignore::load_local(Path::new(project));
+    }
+    let state = app.state::<NexusState>();
+    with_ssh_session(&state, |sess| Ok(Gitignore::load_sftp(sess, Path::new(project))))
+        .await
+        .and_then(|r| r.ok())
+        .unwrap_or_default()
+}
+
+/// Batch raw changes for `DEBOUNCE`, drop ignored paths and emit one
+/// `nexus://fs-change` per changed path
+async fn debounce(app: tauri::AppHandle, project: String, remote: bool, mut rx: UnboundedReceiver<RawChange>) {
+    let mut ignore = load_ignore(&app, &project, remote).await;
+    while let Some(first) = rx.recv().await {
+        let mut pending = BTreeMap::new();
+        coalesce(&mut pending, first);
+        let deadline = tokio::time::Instant::now() + DEBOUNCE;
+        while let Ok(Some(change)) = tokio::time::timeout_at(deadline, rx.recv()).await {
+            coalesce(&mut pending, change);
+        }
+
+        let relative = |path: &str| path.strip_prefix(project.as_str()).unwrap_or(path).trim_start_matches('/').to_string();
+        if pending.keys().any(|p| relative(p) == ".gitignore") {
+            ignore = load_ignore(&app, &project, remote).await;
+        }
+        let timestamp = chrono::Utc::now().to_rfc3339();
+        for (path, (change, is_dir)) in pending {
+            if ignore.is_ignored(&relative(&path), is_dir) {
+                continue;
+            }
+            let _ = app.emit("nexus://fs-change", serde_json::json!({
+                "path": path,
+                "changeType": change,
+                "isDirectory": is_dir,
+                "timestamp": timestamp,
+            }));
+        }
+    }
+}
+
+fn start_local(project: &str, tx: UnboundedSender<RawChange>, alive: Arc<AtomicBool>) -> Result<Backend, String> {
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        let event = match result {
+            Ok(event) => event,
+            Err(e) => {
+                eprintln!("[Tauri] File watcher error: {}", e);
+                return;
+            }
+        };
+        let change = match event.kind {
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => ChangeType::Added,
+            EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => ChangeType::Deleted,
+            EventKind::Modify(ModifyKind::Metadata(_)) | EventKind::Access(_) => return,
+            EventKind::Modify(_) => ChangeType::Modified,
+            _ => return,
+        };
+        for path in event.paths {
+            let is_dir = path.is_dir();
+            let _ = tx.send(RawChange { path: path.to_string_lossy().to_string(), change, is_dir });
+        }
+    }).map_err(|e| e.to_string())?;
+    watcher.watch(Path::new(project), RecursiveMode::Recursive).map_err(|e| e.to_string())?;
+    alive.store(true, Ordering::Relaxed);
+    Ok(Backend::Local(watcher))
+}
+
+/// Parse an inotifywait line in `%e|%w%f` format, e.g. `CREATE,ISDIR|/srv/app/src`
+fn parse_inotify(line: &str) -> Option<RawChange> {
+    let (events, path) = line.split_once('|')?;
+    let has = |name: &str| events.split(',').any(|e| e == name);
+    let change = if has("CREATE") || has("MOVED_TO") {
+        ChangeType::Added
+    } else if has("DELETE") || has("MOVED_FROM") {
+        ChangeType::Deleted
+    } else if has("MODIFY") || has("CLOSE_WRITE") {
+        ChangeType::Modified
+    } else {
+        return None;
+    };
+    Some(RawChange { path: path.to_string(), change, is_dir: has("ISDIR") })
+}
+
+fn start_remote(
+    app: &tauri::AppHandle,
+    creds: crate::SshCredentials,
+    project: &str,
+    tx: UnboundedSender<RawChange>,
+    alive: Arc<AtomicBool>,
+) -> Backend {
+    let command = format!(
+        "inotifywait -m -r -q -e create -e modify -e delete -e move --format '%e|%w%f' --exclude '(^|/)\\.git(/|$)' {}",
+        shell_quote(project)
+    );
+    let mut events = process::spawn_lines(Some(creds), None, &command);
+    let pid = Arc::new(std::sync::Mutex::new(None));
+    let (app, project, pid_slot) = (app.clone(), project.to_string(), pid.clone());
+    alive.store(true, Ordering::Relaxed);
+
+    tauri::async_runtime::spawn(async move {
+        let mut last_line = String::new();
+        while let Some(event) = events.recv().await {
+            match event {
+                ProcessEvent::Started(started) => {
+                    // Checked under the lock `stop` reads the PID with, so
+                    // exactly one side sees it
+                    let stopped = match pid_slot.lock() {
+                        Ok(mut slot) => {
+                            *slot = Some(started);
+                            !alive.load(Ordering::Relaxed)
+                        }
+                        Err(_) => false,
+                    };
+                    if stopped {
+                        let state = app.state::<NexusState>();
+                        let _ = execute_shell_bridge(&format!("kill {}", started), None, &state).await;
+                        break;
+                    }
+                }
+                ProcessEvent::Line(line) => match parse_inotify(&line) {
+                    Some(change) => {
+                        let _ = tx.send(change);
+                    }
+                    None => last_line = line,
+                },
+                ProcessEvent::Exited(code) => {
+                    let stopped = !alive.swap(false, Ordering::Relaxed);
+                    if !stopped {
+                        let error = match code {
+                            127 => "inotifywait is not installed on the remote host (install inotify-tools)".to_string(),
+                            _ if !last_line.is_empty() => last_line.clone(),
+                            _ => format!("inotifywait exited with status {}", code),
+                        };
+                        let _ = app.emit("nexus://fs-watch-error", serde_json::json!({
+                            "project": project,
+                            "error": error,
+                        }));
+                    }
+                }
+                ProcessEvent::Failed(e) => {
+                    alive.store(false, Ordering::Relaxed);
+                    let _ = app.emit("nexus://fs-watch-error", serde_json::json!({
+                        "project": project,
+                        "error": e,
+                    }));
+                }
+            }
+        }
+    });
+    Backend::Remote(pid)
+}
+
+/// Watch `project`, replacing any running watcher. Uses inotifywait over a
+/// dedicated SSH session when connected and the notify crate otherwise.
+pub async fn start(app: &tauri::AppHandle, state: &NexusState, project: String) -> Result<(), String> {
+    stop(state).await;
+
+    let creds = state.ssh_credentials.lock().await.clone();
+    let remote = creds.is_some();
+    let (tx, rx) = unbounded_channel();
+    let alive = Arc::new(AtomicBool::new(false));
+    let backend = match creds {
+        Some(creds) => start_remote(app, creds, &project, tx, alive.clone()),
+        None => start_local(&project, tx, alive.clone())?,
+    };
+    let task = tauri::async_runtime::spawn(debounce(app.clone(), project.clone(), remote, rx));
+
+    eprintln!("[Tauri] Watching {} ({})", project, if remote { "ssh" } else { "local" });
+    *state.file_watcher.lock().await = Some(WatchHandle {
+        project,
+        mode: if remote { "ssh" } else { "local" },
+        alive,
+        backend,
+        task,
+    });
+    Ok(())
+}
+
+/// Stop the running watcher, if any. A remote inotifywait that has not
+/// reported its PID yet is killed as soon as it does.
+pub async fn stop(state: &NexusState) {
+    let Some(handle) = state.file_watcher.lock().await.take() else {
+        return;
+    };
+    handle.alive.store(false, Ordering::Relaxed);
+    handle.task.abort();
+    match handle.backend {
+        Backend::Local(watcher) => drop(watcher),
+        Backend::Remote(pid) => {
+            let pid = pid.lock().ok().and_then(|p| *p);
+            if let Some(pid) = pid {
+                let _ = execute_shell_bridge(&format!("kill {}", pid), None, state).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fold(changes: &[(&str, ChangeType)]) -> Vec<(String, ChangeType)> {
+        let mut pending = BTreeMap::new();
+        for (path, change) in changes {
+            coalesce(&mut pending, RawChange { path: path.to_string(), change: *change, is_dir: false });
+        }
+        pending.into_iter().map(|(path, (change, _))| (path, change)).collect()
+    }
+
+    #[test]
+    fn created_then_deleted_reports_nothing() {
+        assert!(fold(&[("/p/a", ChangeType::Added), ("/p/a", ChangeType::Deleted)]).is_empty());
+    }
+
+    #[test]
+    fn created_then_modified_stays_added() {
+        assert_eq!(
+            fold(&[("/p/a", ChangeType::Added), ("/p/a", ChangeType::Modified)]),
+            vec![("/p/a".to_string(), ChangeType::Added)]
+        );
+    }
+
+    #[test]
+    fn deleted_then_created_is_modified() {
+        // How editors that save by replacing the file show up
+        assert_eq!(
+            fold(&[("/p/a", ChangeType::Deleted), ("/p/a", ChangeType::Added)]),
+            vec![("/p/a".to_string(), ChangeType::Modified)]
+        );
+    }
+
+    #[test]
+    fn paths_are_folded_separately() {
+        assert_eq!(
+            fold(&[
+                ("/p/a", ChangeType::Modified),
+                ("/p/b", ChangeType::Added),
+                ("/p/a", ChangeType::Modified),
+                ("/p/b", ChangeType::Deleted),
+            ]),
+            vec![("/p/a".to_string(), ChangeType::Modified)]
+        );
+    }
+
+    #[test]
+    fn parses_inotify_lines() {
+        let change = parse_inotify("CREATE,ISDIR|/srv/app/src").unwrap();
+        assert_eq!((change.path.as_str(), change.change, change.is_dir), ("/srv/app/src", ChangeType::Added, true));
+
+        let change = parse_inotify("MOVED_FROM|/srv/app/a|b.txt").unwrap();
+        assert_eq!((change.path.as_str(), change.change, change.is_dir), ("/srv/app/a|b.txt", ChangeType::Deleted, false));
+
+        let change = parse_inotify("CLOSE_WRITE,CLOSE|/srv/app/main.rs").unwrap();
+        assert_eq!(change.change, ChangeType::Modified);
+
+        assert!(parse_inotify("OPEN|/srv/app/main.rs").is_none());
+        assert!(parse_inotify("Setting up watches.").is_none());
+    }
+}
@@ -3,12 +3,16 @@
 import { create } from 'zustand';
 import { persist, createJSONStorage } from 'zustand/middleware';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import {
   NexusStatus, SwarmTask, ChatMessage, Agent,
   MemoryStats, WatcherStatus, UserSettings,
-  ConnectionStatus, Toast
+  ConnectionStatus, Toast, FocusTarget
 } from '../types';
 
+// Tauri listeners are registered once, however often init runs
+let listenersReady = false;
+
 interface NexusState {
   // Connection & Status
   backend: {
@@ -98,7 +102,8 @@ interface NexusState {
   loadProviders: () => Promise<void>;
 
   // Business Logic Methods
-  initializeTauriListeners: () => void;
+  initializeTauriListeners: () => Promise<void>;
+  openFocusTarget: (target: FocusTarget) => Promise<void>;
   loadChatHistory: () => Promise<void>;
   loadSwarmTasks: () => Promise<void>;
   loadMemoryStats: () => Promise<void>;
@@ -275,9 +280,31 @@ export const useNexusStore = create<NexusState>()(
       addTerminalOutput: (output) => set((state) => ({ terminalHistory: [...state.terminalHistory, output] })),
 
       // Business Logic Methods
-      initializeTauriListeners: () => {
-        // Event listeners for streaming etc. can be set up here
-        // For now, polling-based updates are used
+      initializeTauriListeners: async () => {
+        if (listenersReady) return;
+        listenersReady = true;
+
+        // Sent the first time the window is focused after a notification
+        await listen<FocusTarget>('nexus://focus-target', (event) => {
+          get().openFocusTarget(event.payload);
+        });
+        // Focus may have come back before the listener was registered
+        try {
+          const pending = await invoke<FocusTarget | null>('take_focus_target');
+          if (pending) await get().openFocusTarget(pending);
+        } catch (e) {
+          console.error('Failed to check for a notification target:', e);
+        }
+      },
+
+      openFocusTarget: async (target) => {
+        if (target.kind === 'swarm') {
+          await get().loadSwarmTasks();
+          const swarm = get().swarmTasks.find((t) => t.id === target.id);
+          if (swarm) set({ currentSwarmTask: swarm });
+        } else if (target.kind === 'conversation') {
+          await get().loadChatHistory();
+        }
       },
 
       loadChatHistory: async () => {
@@ -292,6 +319,11 @@ export const useNexusStore = create<NexusState>()(
                 content: parsed.content,
                 timestamp: parsed.timestamp,
                 isStreaming: parsed.is_streaming || false,
+                metadata: parsed.usage ? {
+                  model: parsed.usage.model ?? undefined,
+                  tokens: parsed.usage.prompt_tokens + parsed.usage.completion_tokens,
+                  latency: parsed.usage.latency_ms,
+                } : undefined,
               };
             } catch {
               return null;
@@ -305,14 +337,25 @@ export const useNexusStore = create<NexusState>()(
 
       loadSwarmTasks: async () => {
         try {
-          const swarmIds: string[] = await invoke('get_all_swarms');
-          const tasks: SwarmTask[] = swarmIds.map((id) => ({
-            id,
-            description: '',
-            status: 'completed' as const,
-            subtasks: [],
-            progress: 100,
-            createdAt: new Date().toISOString(),
+          const swarms: Array<Omit<SwarmTask, 'status'> & { status: string; queuePosition?: number }> =
+            await invoke('get_all_swarms');
+          const statusMap: Record<string, SwarmTask['status']> = {
+            queued: 'pending',
+            running: 'in_progress',
+            paused: 'in_progress',
+            completed: 'completed',
+            failed: 'failed',
+            cancelled: 'cancelled',
+          };
+          const tasks: SwarmTask[] = swarms.map((swarm) => ({
+            id: swarm.id,
+            description: swarm.description,
+            status: statusMap[swarm.status] ?? 'pending',
+            subtasks: swarm.subtasks ?? [],
+            progress: swarm.progress ?? 0,
+            createdAt: swarm.createdAt,
+            startedAt: swarm.startedAt ?? undefined,
+            completedAt: swarm.completedAt ?? undefined,
           }));
           set({ swarmTasks: tasks });
         } catch (e) {
@@ -404,9 +447,9 @@ export const useNexusStore = create<NexusState>()(
           const newTask: SwarmTask = {
             id: parsed.task_id || crypto.randomUUID(),
             description,
-            status: 'completed',
+            status: 'pending',
             subtasks: [],
-            progress: 100,
+            progress: 0,
             createdAt: new Date().toISOString(),
           };
           set((state) => ({ swarmTasks: [...state.swarmTasks, newTask] }));
@@ -35,6 +35,9 @@ export interface WorkerInfo {
   currentTask?: string;
   progress: number;
   lastResult?: string;
+  filesModified?: string[];
+  cost?: number;
+  tokensUsed?: number;
 }
 
 // ============================================================================
@@ -64,10 +67,13 @@ export interface Subtask {
 }
 
 export interface SwarmConfig {
-  maxConcurrentWorkers: number;
+  maxConcurrentWorkers: number;   // Swarms running at once
+  maxParallelSubtasks: number;    // Subtasks each swarm runs at once
   maxRetries: number;
   taskTimeoutSecs: number;
   autoMerge: boolean;
+  plannerModel?: string;
+  defaultCostLimit?: number;
 }
 
 export interface SwarmResult {
@@ -95,6 +101,30 @@ export interface MergeConflict {
   resolution: 'auto_merged' | 'manual_required' | 'skipped';
 }
 
+export type ReviewDecision = 'pending' | 'accepted' | 'rejected';
+
+export interface DiffHunk {
+  index: number;
+  header: string;
+  patch: string;
+  decision: ReviewDecision;
+}
+
+export interface FileDiff {
+  path: string;
+  status: 'added' | 'modified' | 'deleted';
+  binary: boolean;
+  hunks: DiffHunk[];
+  decision: ReviewDecision;
+}
+
+export interface SwarmDiff {
+  taskId: string;
+  base: string;
+  files: FileDiff[];
+  subtasks: { subtaskId: string; files: FileDiff[] }[];
+}
+
 // ============================================================================
 // Chat Types
 // ============================================================================
@@ -140,6 +170,8 @@ export interface FileNode {
   children?: FileNode[];
   size?: number;
   lastModified?: string;
+  isIgnored?: boolean;
+  truncated?: boolean;
   isExpanded?: boolean;
   isSelected?: boolean;
 }
@@ -155,6 +187,97 @@ export interface FileTreeNode extends FileNode {
   };
 }
 
+export interface FileContent {
+  path: string;
+  content?: string;
+  isBinary: boolean;
+  size: number;
+  mtime?: number;
+  hash?: string;
+  offset: number;
+  length: number;
+}
+
+export interface WrittenFile {
+  path: string;
+  size: number;
+  mtime?: number;
+  hash: string;
+}
+
+export interface TransferSummary {
+  id: string;
+  direction: 'upload' | 'download';
+  source: string;
+  destination: string;
+  files: number;
+  bytes: number;
+  skipped: number;
+  resumed: number;
+}
+
+export interface TransferProgress {
+  id: string;
+  direction: 'upload' | 'download';
+  file: string;
+  filesDone: number;
+  filesTotal: number;
+  bytesDone: number;
+  bytesTotal: number;
+}
+
+export interface SearchOptions {
+  path?: string;
+  regex?: boolean;
+  caseSensitive?: boolean;
+  wholeWord?: boolean;
+  include?: string[];
+  exclude?: string[];
+  maxResults?: number;
+}
+
+export interface SearchMatch {
+  path: string;
+  line: number;
+  column: number;
+  matchLength: number;
+  preview: string;
+}
+
+export interface SearchSummary {
+  id: string;
+  engine: 'ripgrep' | 'builtin';
+  matches: number;
+  files: number;
+  truncated: boolean;
+  cancelled: boolean;
+}
+
+export interface ProjectOverrides {
+  provider?: string;
+  model?: string;
+  hierarchyPreset?: string;
+  allowedPaths?: string[];
+}
+
+export interface ProjectMetadata {
+  path: string;
+  repoRoot?: string;
+  languages: string[];
+  tooling: string[];
+  markers: string[];
+}
+
+export interface ProjectEntry {
+  path: string;
+  host?: string;
+  name: string;
+  lastOpened: string;
+  pinned: boolean;
+  overrides: ProjectOverrides;
+  metadata?: ProjectMetadata;
+}
+
 export interface ProjectContext {
   path: string;
   name: string;
@@ -167,12 +290,71 @@ export interface ProjectContext {
 
 export interface GitStatus {
   branch: string;
+  detached?: boolean;
+  head?: string;
+  upstream?: string;
   ahead: number;
   behind: number;
   modified: string[];
   staged: string[];
   untracked: string[];
   conflicted: string[];
+  files?: GitFileStatus[];
+}
+
+export interface GitFileStatus {
+  path: string;
+  origPath?: string;
+  index: string;
+  worktree: string;
+}
+
+export interface GitDiffLine {
+  kind: 'context' | 'add' | 'delete';
+  content: string;
+  oldLine?: number;
+  newLine?: number;
+}
+
+export interface GitFileDiff {
+  path: string;
+  oldPath?: string;
+  status: 'added' | 'modified' | 'deleted' | 'renamed';
+  binary: boolean;
+  additions: number;
+  deletions: number;
+  hunks: Array<{
+    header: string;
+    oldStart: number;
+    newStart: number;
+    lines: GitDiffLine[];
+  }>;
+}
+
+export interface GitCommit {
+  hash: string;
+  shortHash: string;
+  author: string;
+  email: string;
+  date: string;
+  subject: string;
+  refs: string[];
+}
+
+export interface CommitSuggestion {
+  subject: string;
+  body: string;
+  message: string;
+  truncated: boolean;
+}
+
+export interface GitBranch {
+  name: string;
+  isCurrent: boolean;
+  isRemote: boolean;
+  upstream?: string;
+  commit: string;
+  subject: string;
 }
 
 // ============================================================================
@@ -249,6 +431,20 @@ export interface WatcherStatus {
   healingSessionsTotal: number;
   healingSessionsActive: number;
   startTime?: string;
+  fsWatcher?: FsWatcherStatus;
+}
+
+export interface FsWatcherStatus {
+  running: boolean;
+  project?: string;
+  mode?: 'local' | 'ssh';
+}
+
+export interface FsChangeEvent {
+  path: string;
+  changeType: 'added' | 'modified' | 'deleted';
+  isDirectory: boolean;
+  timestamp: string;
 }
 
 export interface ErrorEvent {
@@ -449,6 +645,8 @@ export interface SwarmProgressEvent {
   progress: number;
   status: string;
   currentSubtask?: string;
+  subtask?: Subtask;
+  worker?: WorkerInfo;
 }
 
 export interface ChatStreamEvent {
@@ -487,6 +685,12 @@ export interface Toast {
   duration?: number; // ms, default 5000
 }
 
+// What a native notification was about, from `nexus://focus-target`
+export interface FocusTarget {
+  kind: 'swarm' | 'conversation' | 'daemon';
+  id?: string;
+}
+
 // ============================================================================
 // API Response Types
 // ============================================================================